@@ -0,0 +1,83 @@
+# Writes the game's sound effects as 16-bit mono WAV files next to this script.
+# They are synthesized rather than recorded, so changing one is a matter of
+# editing its function below and running `python3 generate.py`.
+
+import math
+import random
+import struct
+import wave
+from pathlib import Path
+
+RATE = 22050
+
+
+def tone(freq, length, volume=0.5, decay=8.0, shape=math.sin):
+    samples = []
+    for i in range(int(RATE * length)):
+        t = i / RATE
+        samples.append(volume * shape(2 * math.pi * freq * t) * math.exp(-decay * t))
+    return samples
+
+
+def square(phase):
+    return 1.0 if math.sin(phase) >= 0 else -1.0
+
+
+def mix(*parts):
+    """Adds (start time, samples) parts together."""
+    length = max(int(start * RATE) + len(samples) for start, samples in parts)
+    out = [0.0] * length
+    for start, samples in parts:
+        offset = int(start * RATE)
+        for i, sample in enumerate(samples):
+            out[offset + i] += sample
+    return out
+
+
+def reveal():
+    return tone(1200, 0.05, 0.4, decay=80.0)
+
+
+def cascade():
+    # a quick rising run of clicks
+    return mix(*[(i * 0.03, tone(700 + i * 180, 0.06, 0.3, decay=60.0)) for i in range(7)])
+
+
+def flag():
+    return mix((0.0, tone(520, 0.07, 0.4, decay=40.0, shape=square)), (0.02, tone(780, 0.06, 0.2, decay=50.0)))
+
+
+def chord():
+    return mix((0.0, tone(900, 0.05, 0.35, decay=70.0)), (0.06, tone(1350, 0.06, 0.35, decay=60.0)))
+
+
+def explosion():
+    random.seed(1)
+    samples, low = [], 0.0
+    for i in range(int(RATE * 0.9)):
+        t = i / RATE
+        # noise through a falling low-pass filter, with a thump underneath
+        low += (random.uniform(-1.0, 1.0) - low) * max(0.02, 0.5 * math.exp(-4.0 * t))
+        thump = math.sin(2 * math.pi * (60 - 30 * t) * t)
+        samples.append((1.6 * low + 0.6 * thump) * math.exp(-4.5 * t))
+    return samples
+
+
+def fanfare():
+    # C E G and a held high C
+    notes = [(0.0, 523.25, 0.18), (0.15, 659.25, 0.18), (0.3, 783.99, 0.18), (0.45, 1046.5, 0.6)]
+    return mix(*[(start, tone(freq, length, 0.3, decay=3.0, shape=square)) for start, freq, length in notes])
+
+
+def write(name, samples):
+    peak = max(1.0, max(abs(sample) for sample in samples))
+    frames = b"".join(struct.pack("<h", int(32767 * sample / peak)) for sample in samples)
+    with wave.open(str(Path(__file__).with_name(name + ".wav")), "wb") as file:
+        file.setnchannels(1)
+        file.setsampwidth(2)
+        file.setframerate(RATE)
+        file.writeframes(frames)
+
+
+for sound in [reveal, cascade, flag, chord, explosion, fanfare]:
+    write(sound.__name__, sound())
@@ -0,0 +1,76 @@
+//! How long revealing takes on boards from ten thousand to a million tiles.
+//!
+//! "first reveal" is the opening click of a game: placing the mines,
+//! counting the numbers, working out the 3BV and flood filling, with undo
+//! tracking, as `Board::reveal` does. "flood fill" is `reveal_tile` alone,
+//! opening the biggest area around the middle of an already counted grid.
+//!
+//! Run with `cargo bench --bench reveal`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use minesweeper::rules::{count_adjacent_mines, place_mines, reveal_tile};
+use minesweeper::{Board, BoardOptions, Grid, Tile, Topology};
+
+// rows and columns of the square boards measured
+const SIZES: [u16; 4] = [100, 316, 500, 1000];
+// sparse enough for openings spanning most of the board, the worst case for the fill
+const MINE_DENSITY: f64 = 0.05;
+const SEED: u64 = 12345;
+
+fn mines(size: u16) -> u32 {
+    (size as f64 * size as f64 * MINE_DENSITY) as u32
+}
+
+fn first_reveal(c: &mut Criterion) {
+    let mut group = c.benchmark_group("first reveal");
+    group.sample_size(10);
+    for size in SIZES {
+        let grid = Grid::new(size, size, Topology::Square, false);
+        let centre = grid.tile_id(size as i32 / 2, size as i32 / 2).unwrap();
+        group.throughput(Throughput::Elements(grid.tile_count() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(grid.tile_count()), &size, |b, &size| {
+            b.iter_batched(
+                || Board::new(size, size, mines(size), SEED, BoardOptions::default()),
+                |mut board| {
+                    board.reveal(centre);
+                    board
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn flood_fill(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flood fill");
+    group.sample_size(10);
+    for size in SIZES {
+        let grid = Grid::new(size, size, Topology::Square, false);
+        let mut tiles = vec![Tile::default(); grid.tile_count()];
+        let centre = grid.tile_id(size as i32 / 2, size as i32 / 2).unwrap();
+        place_mines(&mut tiles, mines(size), 1, centre, SEED);
+        count_adjacent_mines(&mut tiles, grid);
+        // the opening closest to the middle
+        let start = (centre..tiles.len())
+            .chain(0..centre)
+            .find(|&tile_id| !tiles[tile_id].has_mine() && tiles[tile_id].adjacent_mines == 0)
+            .unwrap();
+
+        group.throughput(Throughput::Elements(grid.tile_count() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(grid.tile_count()), &tiles, |b, tiles| {
+            b.iter_batched(
+                || tiles.clone(),
+                |mut tiles| {
+                    reveal_tile(&mut tiles, start, grid);
+                    tiles
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, first_reveal, flood_fill);
+criterion_main!(benches);
@@ -0,0 +1,307 @@
+// Terminal front-end for Rusty Mines, for playing where no window can be
+// opened (over SSH for example). It plays on the same Board as the macroquad
+// game, so the rules, levels and seeds are identical.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod tui {
+    use std::io::{self, Write};
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+    use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+    use crossterm::{cursor, execute, queue, terminal};
+    use minesweeper::levels::{Level, LEVELS};
+    use minesweeper::{Board, BoardOptions, BoardState, Generation, Topology, WinRule};
+
+    const HELP:&str = "arrows/hjkl move | space reveal | f flag | c chord | u undo | y redo | r restart | m menu | q quit";
+
+    enum Screen {
+        Menu,
+        Playing,
+    }
+
+    struct App {
+        screen:Screen,
+        selected:usize,
+        options:BoardOptions,
+        board:Board,
+        cursor:(u16, u16),
+        // the clock starts on the first reveal and stops when the game ends
+        started:Option<Instant>,
+        finished:Option<Duration>,
+    }
+
+    impl App {
+        fn new() -> Self {
+            let options = BoardOptions::default();
+            Self {
+                screen: Screen::Menu,
+                selected: 0,
+                options,
+                board: LEVELS[0].1.board(new_seed(), options),
+                cursor: (0, 0),
+                started: None,
+                finished: None,
+            }
+        }
+
+        fn start(&mut self, level:Level) {
+            self.board = level.board(new_seed(), self.options);
+            self.cursor = (self.board.rows() / 2, self.board.columns() / 2);
+            self.started = None;
+            self.finished = None;
+            self.screen = Screen::Playing;
+        }
+
+        fn toggle_no_guess(&mut self) {
+            self.options.generation = match self.options.generation {
+                Generation::Random => Generation::NoGuess,
+                Generation::NoGuess => Generation::Random,
+            };
+        }
+
+        fn toggle_hex(&mut self) {
+            self.options.topology = match self.options.topology {
+                Topology::Square => Topology::Hex,
+                Topology::Hex => Topology::Square,
+            };
+        }
+
+        fn toggle_win_rule(&mut self) {
+            self.options.win_rule = match self.options.win_rule {
+                WinRule::RevealSafe => WinRule::FlagMines,
+                WinRule::FlagMines => WinRule::RevealSafe,
+            };
+        }
+
+        fn elapsed(&self) -> Duration {
+            self.finished.or_else(|| self.started.map(|started| started.elapsed())).unwrap_or_default()
+        }
+
+        fn tile_id(&self) -> usize {
+            self.cursor.0 as usize * self.board.columns() as usize + self.cursor.1 as usize
+        }
+
+        // returns false when the player quits
+        fn handle_key(&mut self, key:KeyEvent) -> bool {
+            if key.code == KeyCode::Char('q') || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)) {
+                return false;
+            }
+            match self.screen {
+                Screen::Menu => {
+                    // one entry per level, then the no-guess, hex, wrap and win rule toggles
+                    let entries = LEVELS.len() + 4;
+                    match key.code {
+                        KeyCode::Up | KeyCode::Char('k') => self.selected = (self.selected + entries - 1) % entries,
+                        KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1) % entries,
+                        KeyCode::Enter | KeyCode::Char(' ') if self.selected < LEVELS.len() => self.start(LEVELS[self.selected].1),
+                        KeyCode::Enter | KeyCode::Char(' ') if self.selected == LEVELS.len() => self.toggle_no_guess(),
+                        KeyCode::Enter | KeyCode::Char(' ') if self.selected == LEVELS.len() + 1 => self.toggle_hex(),
+                        KeyCode::Enter | KeyCode::Char(' ') if self.selected == LEVELS.len() + 2 => self.options.wrap = !self.options.wrap,
+                        KeyCode::Enter | KeyCode::Char(' ') if self.selected == LEVELS.len() + 3 => self.toggle_win_rule(),
+                        KeyCode::Char('x') => self.toggle_hex(),
+                        KeyCode::Char('e') => self.options.wrap = !self.options.wrap,
+                        KeyCode::Char('f') => self.toggle_win_rule(),
+                        KeyCode::Char('n') => self.toggle_no_guess(),
+                        KeyCode::Char(c @ '1'..='3') => self.start(LEVELS[c as usize - '1' as usize].1),
+                        _ => (),
+                    }
+                }
+                Screen::Playing => {
+                    let (row, col) = self.cursor;
+                    match key.code {
+                        KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('w') => self.cursor.0 = row.saturating_sub(1),
+                        KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('s') => self.cursor.0 = (row + 1).min(self.board.rows() - 1),
+                        KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('a') => self.cursor.1 = col.saturating_sub(1),
+                        KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('d') => self.cursor.1 = (col + 1).min(self.board.columns() - 1),
+                        KeyCode::Char(' ') | KeyCode::Enter => {
+                            self.started.get_or_insert_with(Instant::now);
+                            self.board.reveal(self.tile_id());
+                        }
+                        KeyCode::Char('f') => {
+                            self.board.flag(self.tile_id());
+                        }
+                        KeyCode::Char('c') => self.board.chord(self.tile_id()),
+                        // undoing the losing move resumes the game as practice
+                        KeyCode::Char('u') => {
+                            let undone = self.board.undo();
+                            if undone {
+                                self.finished = None;
+                            }
+                        }
+                        KeyCode::Char('y') => {
+                            self.board.redo();
+                        }
+                        KeyCode::Char('r') => {
+                            let level = Level { rows: self.board.rows(), columns: self.board.columns(), mines: self.board.mines() };
+                            self.start(level);
+                        }
+                        KeyCode::Char('m') | KeyCode::Esc => self.screen = Screen::Menu,
+                        _ => (),
+                    }
+                    if self.board.is_over() && self.finished.is_none() {
+                        self.finished = Some(self.elapsed());
+                    }
+                }
+            }
+            true
+        }
+
+        fn draw(&self, out:&mut impl Write) -> io::Result<()> {
+            queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+            match self.screen {
+                Screen::Menu => self.draw_menu(out)?,
+                Screen::Playing => self.draw_board(out)?,
+            }
+            out.flush()
+        }
+
+        fn draw_menu(&self, out:&mut impl Write) -> io::Result<()> {
+            let mut lines:Vec<String> = LEVELS
+                .iter()
+                .enumerate()
+                .map(|(i, (name, level))| format!("{}. {:<13} {} x {}, {} mines", i + 1, name, level.columns, level.rows, level.mines))
+                .collect();
+            let no_guess = self.options.generation == Generation::NoGuess;
+            lines.push(format!("   No guessing: {}", if no_guess { "ON" } else { "OFF" }));
+            let hex = self.options.topology == Topology::Hex;
+            lines.push(format!("   Hex grid:    {}", if hex { "ON" } else { "OFF" }));
+            lines.push(format!("   Wrap edges:  {}", if self.options.wrap { "ON" } else { "OFF" }));
+            let flag_to_win = self.options.win_rule == WinRule::FlagMines;
+            lines.push(format!("   Win by:      {}", if flag_to_win { "flagging" } else { "revealing" }));
+
+            queue!(out, SetForegroundColor(Color::DarkYellow), Print("RUSTY MINES"), ResetColor, cursor::MoveToNextLine(2))?;
+            for (i, line) in lines.iter().enumerate() {
+                if i == self.selected {
+                    queue!(out, SetAttribute(Attribute::Reverse), Print(line), SetAttribute(Attribute::Reset))?;
+                } else {
+                    queue!(out, Print(line))?;
+                }
+                queue!(out, cursor::MoveToNextLine(1))?;
+            }
+            queue!(out, cursor::MoveToNextLine(1), Print("arrows select | enter start | n no-guess | x hex | e wrap | f win rule | q quit"))
+        }
+
+        fn draw_board(&self, out:&mut impl Write) -> io::Result<()> {
+            let board = &self.board;
+            let status = match board.state() {
+                BoardState::Playing => "",
+                BoardState::Won => " | YOU WON! r to play again",
+                BoardState::Lost => " | BOOM! r to play again",
+            };
+            // the classic mine counter: mines minus flags placed
+            let practice = if board.practice() { " | PRACTICE" } else { "" };
+            // no-guess generation ran out of time and fell back to random mines
+            let gave_up = board.options().generation == Generation::NoGuess && board.mines_placed() && !board.guess_free();
+            let guessing = if gave_up { " | MAY NEED GUESSING" } else { "" };
+            let header = format!(
+                "MINES: {:>3} | TIME: {:>3} | SEED: {}{}{}{}",
+                board.flags_remaining(),
+                self.elapsed().as_secs(),
+                board.seed(),
+                practice,
+                guessing,
+                status
+            );
+            queue!(out, SetForegroundColor(Color::DarkYellow), Print(header), ResetColor, cursor::MoveToNextLine(1))?;
+
+            let columns = board.columns() as usize;
+            let hex = board.options().topology == Topology::Hex;
+            for tile_id in 0..board.tile_count() {
+                // hex rows are offset by half a tile, which is one character here
+                if hex && tile_id % columns == 0 && (tile_id / columns) % 2 == 1 {
+                    queue!(out, Print(' '))?;
+                }
+                let (symbol, color) = tile_symbol(board, tile_id);
+                let selected = tile_id == self.tile_id();
+                if selected {
+                    queue!(out, SetAttribute(Attribute::Reverse))?;
+                }
+                queue!(out, SetForegroundColor(color), Print(symbol), ResetColor)?;
+                if selected {
+                    queue!(out, SetAttribute(Attribute::Reset))?;
+                }
+                // a space between tiles keeps the grid roughly square
+                queue!(out, Print(' '))?;
+                if tile_id % columns == columns - 1 {
+                    queue!(out, cursor::MoveToNextLine(1))?;
+                }
+            }
+            queue!(out, Print(HELP))
+        }
+    }
+
+    fn tile_symbol(board:&Board, tile_id:usize) -> (char, Color) {
+        let tile = board.tile(tile_id);
+        let lost = board.state() == BoardState::Lost;
+        let won = board.state() == BoardState::Won;
+        match (tile.revealed, tile.flagged(), tile.has_mine()) {
+            // a win shows every mine as flagged, only a loss has an exploded one
+            (_, _, true) if won => ('F', Color::Red),
+            (true, _, true) => ('*', Color::Red),
+            (true, _, false) => match tile.adjacent_mines {
+                0 => ('.', Color::DarkGrey),
+                n => (char::from_digit(n as u32, 10).unwrap_or('?'), number_color(n)),
+            },
+            // after a loss, show the mines and the wrong flags
+            (false, true, false) if lost => ('x', Color::Magenta),
+            (false, true, _) => ('F', Color::Red),
+            (false, false, true) if lost => ('*', Color::White),
+            (false, false, _) => ('#', Color::Grey),
+        }
+    }
+
+    fn number_color(n:u8) -> Color {
+        match n {
+            1 => Color::Blue,
+            2 => Color::Green,
+            3 => Color::Red,
+            4 => Color::DarkBlue,
+            5 => Color::DarkRed,
+            6 => Color::Cyan,
+            7 => Color::Magenta,
+            _ => Color::Grey,
+        }
+    }
+
+    // short seeds like the macroquad game, from the clock since there is no shared RNG here
+    fn new_seed() -> u64 {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos());
+        (nanos % 1_000_000) as u64
+    }
+
+    pub fn run() -> io::Result<()> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        let result = (|| {
+            let mut app = App::new();
+            loop {
+                app.draw(&mut out)?;
+                // wake up regularly so the timer keeps ticking
+                if !event::poll(Duration::from_millis(250))? {
+                    continue;
+                }
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && !app.handle_key(key) {
+                        return Ok(());
+                    }
+                }
+            }
+        })();
+
+        execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+        result
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::io::Result<()> {
+    tui::run()
+}
+
+// there is no terminal on the web
+#[cfg(target_arch = "wasm32")]
+fn main() {}
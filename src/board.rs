@@ -0,0 +1,452 @@
+use crate::rules::{
+    count_adjacent_mines, flag_tile, initialize_grid, place_mines, place_mines_no_guess, reveal_all_adjacent_tiles,
+    reveal_tile, update_game_state, BoardState, WinRule, MAX_MINES_PER_TILE,
+};
+use crate::layout::Layout;
+use crate::metrics::{solved_three_bv, three_bv, Clicks};
+use crate::probability::mine_probabilities;
+use crate::solver::{deduce, Deductions};
+use crate::topology::{Grid, Topology};
+use crate::Tile;
+
+/// How the mines are laid out on the first reveal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Generation {
+    /// Mines go anywhere except the first tile revealed.
+    #[default]
+    Random,
+    /// The layout is re-rolled until the board can be cleared from the first
+    /// click without guessing.
+    NoGuess,
+}
+
+/// Settings that are fixed for the lifetime of a board.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BoardOptions {
+    pub generation: Generation,
+    pub topology: Topology,
+    /// Whether the edges wrap around, see [`Grid`].
+    pub wrap: bool,
+    /// Whether a tile can hold up to [`MAX_MINES_PER_TILE`] mines. The
+    /// solver doesn't handle these boards, so they get no hints, heatmap or
+    /// no-guess layouts.
+    pub multi_mine: bool,
+    pub win_rule: WinRule,
+}
+
+/// A minesweeper game without any rendering attached.
+///
+/// Mines are placed on the first reveal, so the first tile clicked is never a
+/// mine. The layout is fully determined by the seed, the board size, the mine
+/// count, the options and the first tile revealed, so a board can be replayed
+/// by sharing its seed. Tile ids index the grid row by row.
+#[derive(Debug, Clone)]
+pub struct Board {
+    pub(crate) rows: u16,
+    pub(crate) columns: u16,
+    pub(crate) mines: u32,
+    pub(crate) seed: u64,
+    pub(crate) options: BoardOptions,
+    pub(crate) tiles: Vec<Tile>,
+    pub(crate) state: BoardState,
+    pub(crate) mines_flagged: u32,
+    pub(crate) mines_placed: bool,
+    pub(crate) guess_free: bool,
+    pub(crate) hints_used: u32,
+    pub(crate) clicks: Clicks,
+    // computed once the mines are placed
+    pub(crate) three_bv: u32,
+    // set for good once a move has been undone
+    pub(crate) practice: bool,
+    undo_stack: Vec<Change>,
+    redo_stack: Vec<Change>,
+}
+
+// everything a move can change apart from the tiles
+#[derive(Debug, Clone, Copy)]
+struct Progress {
+    state: BoardState,
+    mines_flagged: u32,
+    mines_placed: bool,
+    guess_free: bool,
+    three_bv: u32,
+}
+
+// one undoable move: the tiles it changed, with their old and new values
+#[derive(Debug, Clone)]
+struct Change {
+    tiles: Vec<(usize, Tile, Tile)>,
+    before: Progress,
+    after: Progress,
+}
+
+impl Board {
+    /// Creates an empty board. Panics if the mines would not fit, leaving at
+    /// least one safe tile for the first click.
+    pub fn new(rows: u16, columns: u16, mines: u32, seed: u64, options: BoardOptions) -> Self {
+        let tiles = rows as usize * columns as usize;
+        assert!(tiles > 0, "board must have at least one tile");
+        assert!((mines as usize) < tiles, "{mines} mines do not fit on a {rows}x{columns} board");
+
+        Self {
+            rows,
+            columns,
+            mines,
+            seed,
+            options,
+            tiles: vec![Tile::default(); tiles],
+            state: BoardState::Playing,
+            mines_flagged: 0,
+            mines_placed: false,
+            guess_free: false,
+            hints_used: 0,
+            clicks: Clicks::default(),
+            three_bv: 0,
+            practice: false,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    /// Creates a board with the mines of `layout` already in place, so the
+    /// first reveal doesn't move them and may well hit one. These boards are
+    /// practice games with seed 0, and always classic: `options.multi_mine`
+    /// is ignored.
+    pub fn from_layout(layout: &Layout, options: BoardOptions) -> Self {
+        let options = BoardOptions { multi_mine: false, ..options };
+        let mut board = Self::new(layout.rows, layout.columns, layout.mine_count(), 0, options);
+        for (tile, &mine) in board.tiles.iter_mut().zip(&layout.mines) {
+            tile.mines = mine as u8;
+        }
+        let grid = board.grid();
+        count_adjacent_mines(&mut board.tiles, grid);
+        board.mines_placed = true;
+        board.three_bv = three_bv(&board.tiles, grid);
+        board.practice = true;
+        board
+    }
+
+    /// Clears the board for a new game with the same dimensions and options.
+    pub fn reset(&mut self, seed: u64) {
+        initialize_grid(&mut self.tiles);
+        self.seed = seed;
+        self.state = BoardState::Playing;
+        self.mines_flagged = 0;
+        self.mines_placed = false;
+        self.guess_free = false;
+        self.hints_used = 0;
+        self.clicks = Clicks::default();
+        self.three_bv = 0;
+        self.practice = false;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    pub fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    pub fn columns(&self) -> u16 {
+        self.columns
+    }
+
+    pub fn mines(&self) -> u32 {
+        self.mines
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn options(&self) -> BoardOptions {
+        self.options
+    }
+
+    /// Most mines, and flags, a single tile can hold.
+    pub fn max_mines_per_tile(&self) -> u8 {
+        if self.options.multi_mine {
+            MAX_MINES_PER_TILE
+        } else {
+            1
+        }
+    }
+
+    /// Size and layout of the board, for finding a tile's neighbours.
+    pub fn grid(&self) -> Grid {
+        Grid::new(self.rows, self.columns, self.options.topology, self.options.wrap)
+    }
+
+    pub fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+
+    pub fn tile(&self, tile_id: usize) -> &Tile {
+        &self.tiles[tile_id]
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Converts a row and column to a tile id, if it lies on the board.
+    pub fn tile_id(&self, row: i32, col: i32) -> Option<usize> {
+        self.grid().tile_id(row, col)
+    }
+
+    pub fn state(&self) -> BoardState {
+        self.state
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.state != BoardState::Playing
+    }
+
+    /// Flags placed, right or wrong.
+    pub fn mines_flagged(&self) -> u32 {
+        self.mines_flagged
+    }
+
+    pub fn flags_remaining(&self) -> u32 {
+        self.mines - self.mines_flagged
+    }
+
+    /// Whether the mines have been laid out yet, which happens on the first reveal.
+    pub fn mines_placed(&self) -> bool {
+        self.mines_placed
+    }
+
+    /// True once a no-guess layout has been found for this board. Stays false
+    /// for random boards, and for no-guess boards where generation gave up.
+    pub fn guess_free(&self) -> bool {
+        self.guess_free
+    }
+
+    /// What the solver can prove from the visible numbers and flags.
+    /// Nothing on multi-mine boards.
+    pub fn deductions(&self) -> Deductions {
+        if self.options.multi_mine {
+            return Deductions::default();
+        }
+        deduce(&self.tiles, self.grid(), self.mines)
+    }
+
+    /// Chance of a mine on every tile, given what the player can see. See
+    /// [`mine_probabilities`] for when this returns None; it is always None
+    /// on multi-mine boards.
+    pub fn mine_probabilities(&self) -> Option<Vec<f64>> {
+        if self.options.multi_mine {
+            return None;
+        }
+        mine_probabilities(&self.tiles, self.grid(), self.mines)
+    }
+
+    /// Suggests a tile that is provably safe, counting it as a hint used.
+    /// Before the first reveal any tile is safe, so the centre is suggested.
+    /// Returns None if no safe tile can be deduced.
+    pub fn hint(&mut self) -> Option<usize> {
+        if self.is_over() {
+            return None;
+        }
+        let tile_id = if self.mines_placed {
+            self.deductions().safe.first().copied()?
+        } else {
+            self.tile_id(self.rows as i32 / 2, self.columns as i32 / 2)?
+        };
+        self.hints_used += 1;
+        Some(tile_id)
+    }
+
+    pub fn hints_used(&self) -> u32 {
+        self.hints_used
+    }
+
+    /// Reveals (left), flags (right) and chords made on the board, including
+    /// ones that changed nothing.
+    pub fn clicks(&self) -> Clicks {
+        self.clicks
+    }
+
+    /// Smallest number of clicks that clears the board, once the mines are
+    /// placed. See [`crate::metrics`].
+    pub fn three_bv(&self) -> Option<u32> {
+        self.mines_placed.then_some(self.three_bv)
+    }
+
+    /// How much of the 3BV has been cleared so far.
+    pub fn solved_three_bv(&self) -> u32 {
+        if self.mines_placed {
+            solved_three_bv(&self.tiles, self.grid())
+        } else {
+            0
+        }
+    }
+
+    /// Solved 3BV per second of play.
+    pub fn three_bv_per_second(&self, seconds: f64) -> f64 {
+        if seconds > 0.0 {
+            self.solved_three_bv() as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// IOE, the solved 3BV per click. Chording can push it above 1.0, flags
+    /// and wasted clicks pull it down. None before the first click.
+    pub fn efficiency(&self) -> Option<f64> {
+        let clicks = self.clicks.total();
+        (clicks > 0).then(|| self.solved_three_bv() as f64 / clicks as f64)
+    }
+
+    /// Reveals a tile, placing the mines first if this is the opening move.
+    pub fn reveal(&mut self, tile_id: usize) {
+        if self.is_over() {
+            return;
+        }
+        self.clicks.left += 1;
+        self.track(|board| {
+            let grid = board.grid();
+            if !board.mines_placed {
+                let max_per_tile = board.max_mines_per_tile();
+                match board.options.generation {
+                    Generation::NoGuess if max_per_tile == 1 => {
+                        board.guess_free = place_mines_no_guess(&mut board.tiles, board.mines, grid, tile_id, board.seed);
+                    }
+                    _ => place_mines(&mut board.tiles, board.mines, max_per_tile, tile_id, board.seed),
+                }
+                count_adjacent_mines(&mut board.tiles, grid);
+                board.mines_placed = true;
+                board.three_bv = three_bv(&board.tiles, grid);
+            }
+            reveal_tile(&mut board.tiles, tile_id, grid);
+            board.update_state();
+        });
+    }
+
+    /// Toggles a flag, or on multi-mine boards adds one until the tile is
+    /// full and then clears them. Returns true if the tile changed.
+    pub fn flag(&mut self, tile_id: usize) -> bool {
+        if self.is_over() {
+            return false;
+        }
+        self.clicks.right += 1;
+        self.track(|board| {
+            let flags_before = board.tiles[tile_id].flags as u32;
+            let (flags_remaining, max_flags) = (board.flags_remaining(), board.max_mines_per_tile());
+            let changed = flag_tile(&mut board.tiles, tile_id, flags_remaining, max_flags);
+            if changed {
+                board.mines_flagged = board.mines_flagged + board.tiles[tile_id].flags as u32 - flags_before;
+                board.update_state();
+            }
+            changed
+        })
+    }
+
+    /// Reveals every unflagged neighbour of a revealed number whose flag count
+    /// matches it.
+    pub fn chord(&mut self, tile_id: usize) {
+        if self.is_over() || !self.mines_placed {
+            return;
+        }
+        self.clicks.chord += 1;
+        let grid = self.grid();
+        self.track(|board| {
+            reveal_all_adjacent_tiles(&mut board.tiles, tile_id, grid);
+            board.update_state();
+        });
+    }
+
+    /// True once a move has been undone, and for boards made from a layout.
+    /// Such games are practice and are left out of best times.
+    pub fn practice(&self) -> bool {
+        self.practice
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Takes back the last move, even one that hit a mine or won the game,
+    /// and turns the game into practice. Returns false if there is nothing
+    /// to undo. Clicks stay counted.
+    pub fn undo(&mut self) -> bool {
+        let Some(change) = self.undo_stack.pop() else {
+            return false;
+        };
+        for &(tile_id, before, _) in &change.tiles {
+            self.tiles[tile_id] = before;
+        }
+        self.set_progress(change.before);
+        self.practice = true;
+        self.redo_stack.push(change);
+        true
+    }
+
+    /// Plays the last undone move again. Any new move clears the moves that
+    /// could be redone.
+    pub fn redo(&mut self) -> bool {
+        let Some(change) = self.redo_stack.pop() else {
+            return false;
+        };
+        for &(tile_id, _, after) in &change.tiles {
+            self.tiles[tile_id] = after;
+        }
+        self.set_progress(change.after);
+        self.undo_stack.push(change);
+        true
+    }
+
+    // runs a move, keeping what it changed for undo
+    fn track<T>(&mut self, make_move: impl FnOnce(&mut Self) -> T) -> T {
+        let tiles_before = self.tiles.clone();
+        let before = self.progress();
+        let result = make_move(self);
+
+        let tiles: Vec<(usize, Tile, Tile)> = tiles_before
+            .into_iter()
+            .zip(&self.tiles)
+            .enumerate()
+            .filter(|(_, (before, after))| before != *after)
+            .map(|(tile_id, (before, after))| (tile_id, before, *after))
+            .collect();
+        if !tiles.is_empty() {
+            self.undo_stack.push(Change { tiles, before, after: self.progress() });
+            self.redo_stack.clear();
+        }
+        result
+    }
+
+    fn progress(&self) -> Progress {
+        Progress {
+            state: self.state,
+            mines_flagged: self.mines_flagged,
+            mines_placed: self.mines_placed,
+            guess_free: self.guess_free,
+            three_bv: self.three_bv,
+        }
+    }
+
+    fn set_progress(&mut self, progress: Progress) {
+        self.state = progress.state;
+        self.mines_flagged = progress.mines_flagged;
+        self.mines_placed = progress.mines_placed;
+        self.guess_free = progress.guess_free;
+        self.three_bv = progress.three_bv;
+    }
+
+    fn update_state(&mut self) {
+        if !self.mines_placed {
+            return;
+        }
+        update_game_state(&self.tiles, &mut self.state, self.options.win_rule);
+        if self.state == BoardState::Won {
+            // show the whole board once the game is won
+            for tile in &mut self.tiles {
+                tile.revealed = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Building boards tile by tile, for the level editor.
+//!
+//! The editor keeps a [`Board`] with every tile revealed, so a renderer can
+//! draw it like a game in progress: mines where they were put, and the
+//! number every safe tile would show. The numbers and the board's 3BV are
+//! kept up to date on every change. Edited boards are classic, one mine per
+//! tile, like [`Layout`]s.
+
+use crate::layout::Layout;
+use crate::metrics::three_bv;
+use crate::rules::num_adjacent_mines;
+use crate::solver::is_solvable;
+use crate::{Board, BoardOptions};
+
+#[derive(Debug, Clone)]
+pub struct Editor {
+    board: Board,
+}
+
+impl Editor {
+    /// A board without mines.
+    pub fn new(rows: u16, columns: u16, options: BoardOptions) -> Self {
+        let layout = Layout { rows, columns, mines: vec![false; rows as usize * columns as usize] };
+        Self::from_layout(&layout, options)
+    }
+
+    /// Starts from an existing layout. `options.multi_mine` is ignored.
+    pub fn from_layout(layout: &Layout, options: BoardOptions) -> Self {
+        let mut board = Board::from_layout(layout, options);
+        for tile in &mut board.tiles {
+            tile.revealed = true;
+        }
+        Self { board }
+    }
+
+    /// The board being edited, with every tile revealed.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The mines placed so far.
+    pub fn layout(&self) -> Layout {
+        Layout {
+            rows: self.board.rows,
+            columns: self.board.columns,
+            mines: self.board.tiles.iter().map(|tile| tile.has_mine()).collect(),
+        }
+    }
+
+    /// Adds or removes the mine on a tile. The last safe tile can't be
+    /// mined, since a board needs somewhere to start. Returns true if the
+    /// tile changed.
+    pub fn toggle_mine(&mut self, tile_id: usize) -> bool {
+        let board = &mut self.board;
+        if board.tiles[tile_id].has_mine() {
+            board.tiles[tile_id].mines = 0;
+            board.mines -= 1;
+        } else if (board.mines as usize) < board.tiles.len() - 1 {
+            board.tiles[tile_id].mines = 1;
+            board.mines += 1;
+        } else {
+            return false;
+        }
+
+        self.update_number(tile_id);
+        for next_id in self.board.grid().neighbours(tile_id) {
+            self.update_number(next_id);
+        }
+        self.board.three_bv = three_bv(&self.board.tiles, self.board.grid());
+        true
+    }
+
+    /// Removes every mine.
+    pub fn clear(&mut self) {
+        for tile in &mut self.board.tiles {
+            tile.mines = 0;
+            tile.adjacent_mines = 0;
+        }
+        self.board.mines = 0;
+        self.board.three_bv = three_bv(&self.board.tiles, self.board.grid());
+    }
+
+    /// A tile from which the board can be cleared without guessing, or None
+    /// if it always needs a guess. One tile of every opening is tried, or
+    /// every safe tile on boards without openings.
+    pub fn guess_free_start(&self) -> Option<usize> {
+        let grid = self.board.grid();
+        let tiles = &self.board.tiles;
+        let safe = |tile_id: &usize| !tiles[*tile_id].has_mine();
+        let openings: Vec<usize> = (0..tiles.len()).filter(safe).filter(|&tile_id| tiles[tile_id].adjacent_mines == 0).collect();
+
+        let candidates = if openings.is_empty() {
+            (0..tiles.len()).filter(safe).collect()
+        } else {
+            // one tile per opening, the rest of it gets revealed along with it
+            let mut seen = vec![false; tiles.len()];
+            let mut firsts = Vec::new();
+            for &start in &openings {
+                if seen[start] {
+                    continue;
+                }
+                firsts.push(start);
+                let mut stack = vec![start];
+                seen[start] = true;
+                while let Some(tile_id) = stack.pop() {
+                    for next_id in grid.neighbours(tile_id) {
+                        if !seen[next_id] && safe(&next_id) && tiles[next_id].adjacent_mines == 0 {
+                            seen[next_id] = true;
+                            stack.push(next_id);
+                        }
+                    }
+                }
+            }
+            firsts
+        };
+
+        candidates.into_iter().find(|&tile_id| is_solvable(tiles, grid, self.board.mines, tile_id))
+    }
+
+    fn update_number(&mut self, tile_id: usize) {
+        self.board.tiles[tile_id].adjacent_mines = num_adjacent_mines(&self.board.tiles, self.board.grid(), tile_id);
+    }
+}
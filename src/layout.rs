@@ -0,0 +1,169 @@
+//! Hand-made mine layouts, for loading crafted boards and exporting played ones.
+//!
+//! Two plain-text formats are understood, both with one line per grid row
+//! and one character per tile, `*` for a mine and `.` for a safe tile:
+//!
+//! - the bare text format, which is just those rows;
+//! - the community "RAW board" format, which puts a header in front of them:
+//!
+//! ```text
+//! Width: 8
+//! Height: 3
+//! Mines: 2
+//! Board:
+//! *.......
+//! ........
+//! .......*
+//! ```
+//!
+//! [`Layout::from_text`] tells the two apart by the header. Trailing
+//! whitespace and blank lines around the rows are ignored. Layouts hold at
+//! most one mine per tile.
+
+use crate::save::SaveError;
+use crate::text::Fields;
+use crate::Board;
+
+/// Where the mines of a board are, without any game progress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub rows: u16,
+    pub columns: u16,
+    /// One entry per tile, row by row, true where there is a mine.
+    pub mines: Vec<bool>,
+}
+
+impl Layout {
+    /// Reads a layout in either format.
+    pub fn from_text(text: &str) -> Result<Self, SaveError> {
+        let mut lines = text.lines().map(str::trim_end).skip_while(|line| line.trim().is_empty()).peekable();
+        if !lines.peek().is_some_and(|line| line.starts_with("Width:")) {
+            return Self::from_rows(lines);
+        }
+
+        let fields = Fields::read(&mut lines, ':', "Board:")?;
+        let width: u16 = fields.parse("Width")?;
+        let height: u16 = fields.parse("Height")?;
+        let mines: u32 = fields.parse("Mines")?;
+
+        let layout = Self::from_rows(lines)?;
+        if layout.rows != height || layout.columns != width {
+            return Err(SaveError::Invalid("board size", format!("{}x{}, header says {height}x{width}", layout.rows, layout.columns)));
+        }
+        if layout.mine_count() != mines {
+            return Err(SaveError::Invalid("Mines", format!("{mines}, board has {}", layout.mine_count())));
+        }
+        Ok(layout)
+    }
+
+    // the grid rows, up to the first blank line
+    fn from_rows<'a>(lines: impl Iterator<Item = &'a str>) -> Result<Self, SaveError> {
+        let mut mines = Vec::new();
+        let mut rows: u16 = 0;
+        let mut columns = None;
+        for line in lines.take_while(|line| !line.trim().is_empty()) {
+            if *columns.get_or_insert(line.len()) != line.len() {
+                return Err(SaveError::Invalid("row", line.to_owned()));
+            }
+            for c in line.chars() {
+                match c {
+                    '*' => mines.push(true),
+                    '.' => mines.push(false),
+                    _ => return Err(SaveError::Invalid("row", line.to_owned())),
+                }
+            }
+            rows = rows.checked_add(1).ok_or(SaveError::Invalid("board size", "too many rows".to_owned()))?;
+        }
+
+        let columns = columns.ok_or(SaveError::Missing("board"))?;
+        let columns = u16::try_from(columns).map_err(|_| SaveError::Invalid("board size", format!("{columns} columns")))?;
+        let layout = Self { rows, columns, mines };
+        if layout.mine_count() as usize == layout.mines.len() {
+            return Err(SaveError::Invalid("board size", "no safe tile".to_owned()));
+        }
+        Ok(layout)
+    }
+
+    /// The mines of a board, or None before they are placed and on
+    /// multi-mine boards.
+    pub fn from_board(board: &Board) -> Option<Self> {
+        if !board.mines_placed || board.options.multi_mine {
+            return None;
+        }
+        Some(Self {
+            rows: board.rows,
+            columns: board.columns,
+            mines: board.tiles.iter().map(|tile| tile.has_mine()).collect(),
+        })
+    }
+
+    pub fn mine_count(&self) -> u32 {
+        self.mines.iter().filter(|&&mine| mine).count() as u32
+    }
+
+    /// Writes the bare rows.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for row in self.mines.chunks(self.columns as usize) {
+            text.extend(row.iter().map(|&mine| if mine { '*' } else { '.' }));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Writes the rows behind a RAW board header.
+    pub fn to_raw(&self) -> String {
+        format!("Width: {}\nHeight: {}\nMines: {}\nBoard:\n{}", self.columns, self.rows, self.mine_count(), self.to_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> Layout {
+        Layout { rows: 3, columns: 4, mines: "*.......*..*".chars().map(|c| c == '*').collect() }
+    }
+
+    #[test]
+    fn both_formats_read_back_what_they_write() {
+        let layout = layout();
+        assert_eq!(layout.to_text(), "*...\n....\n*..*\n");
+        assert_eq!(Layout::from_text(&layout.to_text()), Ok(layout.clone()));
+        assert_eq!(layout.to_raw(), "Width: 4\nHeight: 3\nMines: 3\nBoard:\n*...\n....\n*..*\n");
+        assert_eq!(Layout::from_text(&layout.to_raw()), Ok(layout));
+    }
+
+    #[test]
+    fn blank_lines_and_trailing_spaces_around_the_rows_are_ignored() {
+        assert_eq!(Layout::from_text("\n\n*...  \n....\n*..*\n\n"), Ok(layout()));
+        assert_eq!(Layout::from_text("Width:4\nHeight:  3\nMines: 3\nBoard:\n*...\n....\n*..*\n\n"), Ok(layout()));
+    }
+
+    #[test]
+    fn malformed_layouts_say_what_is_wrong() {
+        assert_eq!(Layout::from_text(""), Err(SaveError::Missing("board")));
+        assert_eq!(Layout::from_text("*..\n..\n"), Err(SaveError::Invalid("row", "..".to_owned())));
+        assert_eq!(Layout::from_text("*.x\n"), Err(SaveError::Invalid("row", "*.x".to_owned())));
+        assert_eq!(Layout::from_text("**\n**\n"), Err(SaveError::Invalid("board size", "no safe tile".to_owned())));
+    }
+
+    #[test]
+    fn raw_headers_must_match_the_board() {
+        let rows = "Board:\n*...\n....\n*..*\n";
+        assert_eq!(Layout::from_text(&format!("Width: 4\nMines: 3\n{rows}")), Err(SaveError::Missing("Height")));
+        assert_eq!(
+            Layout::from_text(&format!("Width: four\nHeight: 3\nMines: 3\n{rows}")),
+            Err(SaveError::Invalid("Width", "four".to_owned()))
+        );
+        assert_eq!(
+            Layout::from_text(&format!("Width: 3\nHeight: 4\nMines: 3\n{rows}")),
+            Err(SaveError::Invalid("board size", "3x4, header says 4x3".to_owned()))
+        );
+        assert_eq!(
+            Layout::from_text(&format!("Width: 4\nHeight: 3\nMines: 2\n{rows}")),
+            Err(SaveError::Invalid("Mines", "2, board has 3".to_owned()))
+        );
+        assert_eq!(Layout::from_text("Width: 4\nHeight 3\n"), Err(SaveError::Invalid("line", "Height 3".to_owned())));
+    }
+}
@@ -0,0 +1,43 @@
+//! The difficulty levels offered by every front-end.
+
+use crate::{Board, BoardOptions, Topology, WinRule};
+
+/// Board dimensions and mine count of a difficulty level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Level {
+    pub rows: u16,
+    pub columns: u16,
+    pub mines: u32,
+}
+
+impl Level {
+    /// A fresh board of this level.
+    pub fn board(&self, seed: u64, options: BoardOptions) -> Board {
+        Board::new(self.rows, self.columns, self.mines, seed, options)
+    }
+}
+
+pub const BEGINNER: Level = Level { rows: 8, columns: 8, mines: 10 };
+pub const INTERMEDIATE: Level = Level { rows: 16, columns: 16, mines: 40 };
+pub const EXPERT: Level = Level { rows: 16, columns: 30, mines: 99 };
+
+/// The classic levels by name, easiest first.
+pub const LEVELS: [(&str, Level); 3] = [("Beginner", BEGINNER), ("Intermediate", INTERMEDIATE), ("Expert", EXPERT)];
+
+/// Name of the classic level a board was made for, or `"Custom"`. Hex,
+/// wrapping, multi-mine and flag-to-win boards are always custom, so they
+/// stay out of the classic leaderboards.
+pub fn level_name(board: &Board) -> &'static str {
+    let options = board.options();
+    let variant = options.topology != Topology::Square
+        || options.wrap
+        || options.multi_mine
+        || options.win_rule != WinRule::RevealSafe;
+    if variant {
+        return "Custom";
+    }
+    LEVELS
+        .iter()
+        .find(|(_, level)| level.rows == board.rows() && level.columns == board.columns() && level.mines == board.mines())
+        .map_or("Custom", |(name, _)| name)
+}
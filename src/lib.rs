@@ -0,0 +1,25 @@
+//! Headless minesweeper engine behind Rusty Mines.
+//!
+//! Nothing in here depends on macroquad, so tools, bots and tests can play
+//! full games through [`Board`] without opening a window. The graphical game
+//! in `main.rs` is a renderer on top of this crate.
+
+mod board;
+pub mod editor;
+pub mod layout;
+pub mod levels;
+pub mod metrics;
+pub mod probability;
+pub mod replay;
+pub mod rules;
+pub mod save;
+pub mod solver;
+pub mod stats;
+mod text;
+mod tile;
+pub mod topology;
+
+pub use board::{Board, BoardOptions, Generation};
+pub use rules::{BoardState, WinRule};
+pub use tile::Tile;
+pub use topology::{Grid, Topology};
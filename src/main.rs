@@ -1,6 +1,20 @@
 use macroquad::prelude::*;
-use macroquad::rand::srand;
-use macroquad::rand::gen_range;
+use macroquad::rand::{gen_range, srand};
+use minesweeper::editor::Editor;
+use minesweeper::layout::Layout;
+use minesweeper::levels::{level_name, Level, EXPERT, LEVELS};
+use minesweeper::replay::{ActionKind, Recording};
+use minesweeper::save::SavedGame;
+use minesweeper::stats::{date_from_unix, GameRecord, Stats};
+use minesweeper::{Board, BoardOptions, BoardState, Generation, Grid, Topology, WinRule};
+use settings::{Settings, UI_SCALES};
+use sound::{Effect, Sounds};
+use theme::{ColorVision, Palette, SafeColors, Theme};
+
+mod settings;
+mod sound;
+mod storage;
+mod theme;
 
 /*************************************************************
 Classic minesweeper levels:
@@ -10,69 +24,322 @@ Expert       -> 30 x 16 grid, 99 mines
 *************************************************************/
 
 // TODO:
-// - Check if all mines are correctly flagged and trigger win game
 // - Improve main manu and add level selection
 // - Clean-up draw_grid function, match on game state
-// - Move grid, tile, input handling etc code in own modules
 
 const WINDOW_WIDTH:i32 = 600;
 const WINDOW_HEIGHT:i32 = 600;
 const MAX_TILE_SIZE:f32 = 80.0;
-const BACKGROUND:Color = Color::new(0.05, 0.05, 0.05, 1.0);
+// space kept free above the grid for the top panel, and around the grid
+const TOP_PANEL_HEIGHT:f32 = 50.0;
+const GRID_MARGIN:f32 = 10.0;
+// height of a hexagonal tile and the distance between its rows, per tile width
+const HEX_HEIGHT:f32 = 1.154_700_5;
+const HEX_ROW_PITCH:f32 = 0.866_025_4;
+const SAVE_KEY:&str = "game.sav";
+// moves of the saved game, so a continued game can still be replayed
+const SAVE_REPLAY_KEY:&str = "game.replay";
+const LAST_REPLAY_KEY:&str = "last.replay";
+const STATS_KEY:&str = "stats.csv";
+const SETTINGS_KEY:&str = "settings.txt";
+// the player's own theme, shown after the built-in ones
+const THEME_KEY:&str = "theme.txt";
+// hand-made board for "Load board", and where E writes the current one
+const LAYOUT_KEY:&str = "board.txt";
+const EXPORT_KEY:&str = "export.txt";
+const EXPORT_RAW_KEY:&str = "export.raw";
+const LEADERBOARD_SIZE:usize = 10;
+
+// limits for the custom board screen
+const MAX_CUSTOM_SIZE:u16 = 500;
+const MAX_MINE_DENSITY:f32 = 0.8;
+const CUSTOM_FIELDS:[&str; 3] = ["Rows", "Columns", "Mines"];
+
+// text typed into the custom board screen
+struct CustomBoard {
+    inputs:[String; 3],
+    editing:Option<usize>,
+}
+
+impl CustomBoard {
+    fn new(level:Level) -> Self {
+        Self {
+            inputs: [level.rows.to_string(), level.columns.to_string(), level.mines.to_string()],
+            editing: None,
+        }
+    }
 
-#[allow(dead_code)]
+    // the typed level, or why it can't be played
+    fn level(&self) -> Result<Level, String> {
+        let [rows, columns, mines] = &self.inputs;
+        let size = |name:&str, text:&str| match text.parse::<u16>() {
+            Ok(n) if (2..=MAX_CUSTOM_SIZE).contains(&n) => Ok(n),
+            _ => Err(format!("{} must be between 2 and {}", name, MAX_CUSTOM_SIZE)),
+        };
+        let rows = size("Rows", rows)?;
+        let columns = size("Columns", columns)?;
+
+        // the first click and its neighbours are always kept free of mines
+        let tiles = rows as u32 * columns as u32;
+        let max_mines = ((tiles as f32 * MAX_MINE_DENSITY) as u32).min(tiles.saturating_sub(9));
+        match mines.parse::<u32>() {
+            Ok(mines) if (1..=max_mines).contains(&mines) => Ok(Level { rows, columns, mines }),
+            _ if max_mines == 0 => Err("Board is too small for any mines".to_owned()),
+            _ => Err(format!("Mines must be between 1 and {}", max_mines)),
+        }
+    }
+}
 
-struct Level {
-    rows:u8,
-    columns:u8,
-    mines:u16,
+// the fastest win is kept per board size and shape
+fn best_replay_key(rows:u16, columns:u16, mines:u32, options:BoardOptions) -> String {
+    let hex = if options.topology == Topology::Hex { "hex-" } else { "" };
+    let wrap = if options.wrap { "wrap-" } else { "" };
+    let multi = if options.multi_mine { "multi-" } else { "" };
+    let flag = if options.win_rule == WinRule::FlagMines { "flag-" } else { "" };
+    format!("best-{}{}{}{}{}x{}-{}.replay", hex, wrap, multi, flag, rows, columns, mines)
 }
 
-const LEVEL_1:Level = Level { rows: 8, columns: 8, mines: 10 };
-const LEVEL_2:Level = Level { rows: 12, columns: 12, mines: 24 };
-const LEVEL_3:Level = Level { rows: 16, columns: 16, mines: 40 };
+// every combination of the options that get their own best replays
+fn board_variants() -> impl Iterator<Item = BoardOptions> {
+    (0..16).map(|bits| BoardOptions {
+        topology: if bits & 1 != 0 { Topology::Hex } else { Topology::Square },
+        wrap: bits & 2 != 0,
+        multi_mine: bits & 4 != 0,
+        win_rule: if bits & 8 != 0 { WinRule::FlagMines } else { WinRule::RevealSafe },
+        ..BoardOptions::default()
+    })
+}
+
+// name for the board variants that have their own best replays
+fn variant_name(options:BoardOptions) -> String {
+    let mut name = String::new();
+    if options.win_rule == WinRule::FlagMines {
+        name += "flag-to-win ";
+    }
+    if options.multi_mine {
+        name += "multi-mine ";
+    }
+    if options.wrap {
+        name += "wrapped ";
+    }
+    if options.topology == Topology::Hex {
+        name += "hex ";
+    }
+    name
+}
 
+// the board itself lives in the engine crate, this only adds what the renderer needs
 struct Game {
-    rows:u8,
-    columns:u8,
-    tiles:u16,
-    cell_size:f32,
-    mines:u16,
-    mines_flagged:u16,
-    level_time:u64,
-    mines_placed_in_grid:bool,
+    board:Board,
+    hint:Option<usize>,
+    // mine probabilities for the heatmap, recomputed when stale
+    heatmap:Option<Vec<f64>>,
+    heatmap_stale:bool,
+    // keyboard cursor, hidden until a movement key is pressed
+    cursor:Option<usize>,
+    // None for continued games whose earlier moves were not recorded
+    recording:Option<Recording>,
+    // a board from the editor, played to try it out and kept out of the statistics
+    test_play:bool,
+    // the best replay this game's win took the place of, None if it set none;
+    // put back if the win is undone
+    replaced_best:Option<Option<String>>,
 }
 
 impl Game {
-    fn new(rows:u8, columns:u8, mines:u16) -> Self {
+    fn new(rows:u16, columns:u16, mines:u32, seed:u64, options:BoardOptions) -> Self {
+        Self::from_board(Board::new(rows, columns, mines, seed, options))
+    }
+
+    fn from_board(board:Board) -> Self {
+        Self {
+            recording: (!board.mines_placed()).then(|| Recording::new(&board)),
+            board,
+            hint: None,
+            heatmap: None,
+            heatmap_stale: true,
+            cursor: None,
+            test_play: false,
+            replaced_best: None,
+        }
+    }
+
+    fn reset(&mut self, seed:u64) {
+        self.board.reset(seed);
+        self.hint = None;
+        self.heatmap = None;
+        self.heatmap_stale = true;
+        self.recording = Some(Recording::new(&self.board));
+        self.replaced_best = None;
+    }
+
+    fn record(&mut self, kind:ActionKind, tile_id:usize, time:f64) {
+        if let Some(recording) = &mut self.recording {
+            recording.record(kind, tile_id, time);
+        }
+    }
+
+    // Z takes back the last move and Y plays it again; either makes the game a practice game,
+    // so a win that is undone gives back the best replay it set
+    fn undo(&mut self, time:f64) -> bool {
+        let changed = self.board.undo();
+        if changed {
+            self.record(ActionKind::Undo, 0, time);
+            if let Some(replaced) = self.replaced_best.take() {
+                let key = best_replay_key(self.board.rows(), self.board.columns(), self.board.mines(), self.board.options());
+                match replaced {
+                    Some(text) => {
+                        let _ = storage::save(&key, &text);
+                    }
+                    None => storage::remove(&key),
+                }
+            }
+        }
+        changed
+    }
+
+    fn redo(&mut self, time:f64) -> bool {
+        let changed = self.board.redo();
+        if changed {
+            self.record(ActionKind::Redo, 0, time);
+        }
+        changed
+    }
+
+    // keeps the finished game as the last replay, and as the best one if it was the fastest win
+    fn save_replay(&mut self) {
+        let Some(recording) = &self.recording else {
+            return;
+        };
+        let _ = storage::save(LAST_REPLAY_KEY, &recording.to_text());
+
+        // practice games can't set records
+        if self.board.state() == BoardState::Won && !self.board.practice() {
+            let key = best_replay_key(self.board.rows(), self.board.columns(), self.board.mines(), self.board.options());
+            let previous = storage::load(&key);
+            let best = previous.as_deref().and_then(|text| Recording::from_text(text).ok());
+            if best.is_none_or(|best| recording.duration() < best.duration()) {
+                let _ = storage::save(&key, &recording.to_text());
+                self.replaced_best = Some(previous);
+            }
+        }
+    }
+}
+
+// plays back a recording through draw_grid
+struct ReplayViewer {
+    name:String,
+    recording:Recording,
+    // board after the first move, so stepping back doesn't place the mines again
+    opening:Board,
+    board:Board,
+    applied:usize,
+    time:f64,
+    playing:bool,
+    speed:f64,
+}
+
+impl ReplayViewer {
+    fn new(name:String, recording:Recording) -> Self {
         Self {
-            rows,
-            columns,
-            tiles: rows as u16 * columns as u16,
-            cell_size: calculate_tile_size(rows, columns, MAX_TILE_SIZE),
-            mines,
-            mines_flagged: 0,
-            level_time: 0,
-            mines_placed_in_grid: false,
+            name,
+            opening: recording.board_after(1),
+            board: recording.start_board(),
+            recording,
+            applied: 0,
+            time: 0.0,
+            playing: true,
+            speed: 1.0,
+        }
+    }
+
+    fn seek(&mut self, count:usize) {
+        let count = count.min(self.recording.actions.len());
+        if count < self.applied {
+            self.board = if count == 0 { self.recording.start_board() } else { self.opening.clone() };
+            self.applied = count.min(1);
+        }
+        while self.applied < count {
+            self.recording.actions[self.applied].apply(&mut self.board);
+            self.applied += 1;
+        }
+    }
+
+    fn step(&mut self, forward:bool) {
+        self.playing = false;
+        let count = if forward { self.applied + 1 } else { self.applied.saturating_sub(1) };
+        self.seek(count);
+        self.time = self.applied.checked_sub(1).map_or(0.0, |i| self.recording.actions[i].time);
+    }
+
+    fn update(&mut self, dt:f64) {
+        if !self.playing {
+            return;
+        }
+        self.time += dt * self.speed;
+        let count = self.recording.actions.iter().take_while(|action| action.time <= self.time).count();
+        self.seek(count);
+        if self.applied == self.recording.actions.len() {
+            self.playing = false;
+        }
+    }
+
+    fn last_tile(&self) -> Option<usize> {
+        self.applied.checked_sub(1).map(|i| self.recording.actions[i].tile_id)
+    }
+}
+
+// the last game and the best win of each level, whichever exist
+fn load_replays() -> Vec<(String, Recording)> {
+    let mut replays = Vec::new();
+    if let Some(recording) = storage::load(LAST_REPLAY_KEY).and_then(|text| Recording::from_text(&text).ok()) {
+        replays.push(("Last game".to_owned(), recording));
+    }
+    for (name, level) in LEVELS {
+        for options in board_variants() {
+            let key = best_replay_key(level.rows, level.columns, level.mines, options);
+            if let Some(recording) = storage::load(&key).and_then(|text| Recording::from_text(&text).ok()) {
+                replays.push((format!("Best {}{}", variant_name(options), name), recording));
+            }
         }
     }
+    replays
+}
+
+// what a main menu button does when clicked
+#[derive(Clone, Copy, PartialEq)]
+enum MenuAction {
+    Continue,
+    Play(Level),
+    ToggleNoGuess,
+    ToggleHex,
+    ToggleWrap,
+    ToggleMultiMine,
+    ToggleWinRule,
+    EditSeed,
+    Replays,
+    Custom,
+    LoadBoard,
+    Editor,
+    Statistics,
+    Settings,
 }
 
 #[derive(PartialEq)]
 enum GameState {
     MeinMenu,
+    CustomMenu,
     GameWon,
     GameLost,
     GameRunning,
+    Replay,
+    Statistics,
+    Editor,
+    Settings,
+    Sound,
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Tile {
-    revealed: bool,
-    has_mine: bool,
-    flagged: bool,
-    adjacent_mines:u8,
-}
+
 struct Assets {
     one: Rect,
     two: Rect,
@@ -85,47 +352,135 @@ struct Assets {
     bomb:Rect,
     explosion:Rect,
     flag:Rect,
+    // 2 and 3 of them on one tile, for multi-mine boards
+    flag2:Rect,
+    flag3:Rect,
+    bomb2:Rect,
+    bomb3:Rect,
     spritesheet:Texture2D,
     font:Font,
+    colors:Palette,
+    // accessibility settings, which change how the board is drawn
+    number_colors:[Color; 8],
+    heatmap:(Color, Color),
+    bold_outlines:bool,
+    patterned_tiles:bool,
+    text_numbers:bool,
 }
 
-impl Default for Assets{
-    fn default() -> Self {
+impl Assets {
+    // the sheet, font and colours of a theme, with the accessibility settings applied.
+    // Sheets and fonts of the player's own themes are read from next to the save, the
+    // built-in ones are embedded into the binary.
+    fn new(theme:&Theme, settings:&Settings) -> Result<Self, String> {
         set_pc_assets_folder("../assets");
 
-        // embed the spritesheet into the binary exe file
-        let spritesheet = Texture2D::from_file_with_format(include_bytes!("../assets/minesweeper.png"), Some(ImageFormat::Png));
-        let font = load_ttf_font_from_bytes(include_bytes!("../assets/Sono-ExtraBold.ttf")).unwrap();
+        let sheet = match &theme.sprite_sheet {
+            Some(file) => storage::load_bytes(file).ok_or(format!("No {} found next to the save", file))?,
+            None => include_bytes!("../assets/minesweeper.png").to_vec(),
+        };
+        let mut image = Image::from_file_with_format(&sheet, Some(ImageFormat::Png)).map_err(|err| format!("Could not read the sprite sheet: {}", err))?;
+        let sprites = &theme.sprites;
+        if sprites.all().any(|sprite| sprite.right() > image.width as f32 || sprite.bottom() > image.height as f32) {
+            return Err(format!("The sprites of {} don't fit in its sprite sheet", theme.name));
+        }
+        let font = match &theme.font {
+            Some(file) => storage::load_bytes(file).ok_or(format!("No {} found next to the save", file))?,
+            None => include_bytes!("../assets/Sono-ExtraBold.ttf").to_vec(),
+        };
+        let font = load_ttf_font_from_bytes(&font).map_err(|err| format!("Could not read the font: {}", err))?;
+
+        // colour blind palettes take over from the colours in the theme and the sprite sheet
+        let mut colors = theme.colors.clone();
+        let mut number_colors = [colors.number; 8];
+        let mut heatmap = (Color::new(0.0, 1.0, 0.0, 1.0), Color::new(1.0, 0.0, 0.0, 1.0));
+        if let Some(safe) = SafeColors::for_vision(settings.color_vision) {
+            for (&sprite, &color) in sprites.numbers.iter().zip(&safe.numbers) {
+                recolor_sprite(&mut image, sprite, color);
+            }
+            number_colors = safe.numbers;
+            heatmap = (safe.safe, safe.mine);
+            colors.hint = safe.hint;
+            colors.success = safe.success;
+            colors.alert = safe.alert;
+        }
+
+        let (bomb, flag) = (sprites.bomb, sprites.flag);
+        let (image, stacked) = add_stacked_sprites(&image, &[(flag, 2), (flag, 3), (bomb, 2), (bomb, 3)]);
+        let spritesheet = Texture2D::from_image(&image);
         spritesheet.set_filter(FilterMode::Nearest);
         build_textures_atlas();
 
-        Self {
-            one:Rect::new(0.0, 0.0, 40.0, 40.0),
-            two:Rect::new(41.0, 0.0, 40.0, 40.0),
-            three:Rect::new(82.0, 0.0, 40.0, 40.0),
-            four:Rect::new(123.0, 0.0, 40.0, 40.0),
-            five:Rect::new(0.0, 41.0, 40.0, 40.0),
-            six:Rect::new(41.0, 41.0, 40.0, 40.0),
-            seven:Rect::new(82.0, 41.0, 40.0, 40.0),
-            eight:Rect::new(123.0, 41.0, 40.0, 40.0),
-            bomb:Rect::new(0.0, 82.0, 40.0, 40.0),
-            explosion:Rect::new(41.0, 82.0, 40.0, 40.0),
-            flag:Rect::new(82.0, 82.0, 40.0, 40.0),
+        let [one, two, three, four, five, six, seven, eight] = sprites.numbers;
+        Ok(Self {
+            one,
+            two,
+            three,
+            four,
+            five,
+            six,
+            seven,
+            eight,
+            bomb,
+            explosion:sprites.explosion,
+            flag,
+            flag2:stacked[0],
+            flag3:stacked[1],
+            bomb2:stacked[2],
+            bomb3:stacked[3],
             spritesheet,
             font,
+            colors,
+            number_colors,
+            heatmap,
+            bold_outlines:settings.bold_outlines,
+            patterned_tiles:settings.patterned_tiles,
+            text_numbers:settings.text_numbers,
+        })
+    }
 
+    fn flags(&self, count:u8) -> Rect {
+        match count {
+            1 => self.flag,
+            2 => self.flag2,
+            _ => self.flag3,
         }
     }
-}
 
-impl Assets {
+    fn bombs(&self, count:u8) -> Rect {
+        match count {
+            1 => self.bomb,
+            2 => self.bomb2,
+            _ => self.bomb3,
+        }
+    }
+
+    // numbers above 8 only happen on multi-mine boards and have no sprite, the
+    // others are written out like this when the sprites are turned off
+    fn draw_number(&self, number:u8, x:f32, y:f32, tile_size:f32) {
+        let text = number.to_string();
+        let font_size = (tile_size / 1.8) as u16;
+        let dimensions = measure_text(&text, Some(&self.font), font_size, 1.0);
+        draw_text_ex(
+            &text,
+            x - dimensions.width / 2.0,
+            y - dimensions.height / 2.0 + dimensions.offset_y,
+            TextParams {
+                font: Some(&self.font),
+                font_size,
+                color: self.number_colors.get(number as usize - 1).copied().unwrap_or(self.colors.number),
+                ..Default::default()
+            }
+        );
+    }
+
     fn draw(&self, rect:Rect, x:f32, y:f32, tile_size:f32){
         let size = tile_size / 1.5;
         draw_texture_ex(
             &self.spritesheet, 
             x - size / 2.0, 
             y - size / 2.0, 
-            WHITE,
+            self.colors.sprite_tint,
             DrawTextureParams {
                 source: Some(rect),
                 dest_size: Some(Vec2::new(size, size)),
@@ -135,265 +490,506 @@ impl Assets {
     }
 }
 
-fn calculate_tile_size(rows:u8, columns:u8, max_tile_size:f32) -> f32 {
-    let screen_width = screen_width();
-    let screen_height = screen_height();
-
-    ((screen_width / columns as f32).min(screen_height / (rows as f32 + 2.0))).min(max_tile_size) // adding space for the top menu and some margin
+// paints the opaque pixels of a sprite in one colour, keeping its edges
+fn recolor_sprite(image:&mut Image, sprite:Rect, color:Color) {
+    for y in sprite.y as u32..sprite.bottom() as u32 {
+        for x in sprite.x as u32..sprite.right() as u32 {
+            let alpha = image.get_pixel(x, y).a;
+            if alpha > 0.0 {
+                image.set_pixel(x, y, Color { a: alpha, ..color });
+            }
+        }
+    }
 }
 
-// calculate grid offsets to center the grid on the screen
-fn calculate_grid_offsets(rows:u8, columns:u8, max_tile_size:f32) -> (f32, f32) {
-    let tile_size = calculate_tile_size(rows, columns, max_tile_size);
-    let x_offset = (screen_width() - columns as f32 * tile_size) / 2.0;
-    let y_offset = (screen_height() - (rows - 1) as f32 * tile_size) / 2.0; // adding space for the top menu and some margin
-    (x_offset, y_offset)
-}
+// spritesheet with a row below it of sprites drawn 2 or 3 times on one tile, smaller and
+// overlapping, returning where each of them went
+fn add_stacked_sprites(sheet:&Image, sprites:&[(Rect, usize)]) -> (Image, Vec<Rect>) {
+    const CELL:u16 = 40;
+    const SMALL:u16 = 28;
+    let width = sheet.width.max(sprites.len() as u16 * (CELL + 1));
+    let mut image = Image::gen_image_color(width, sheet.height + CELL + 1, Color::new(0.0, 0.0, 0.0, 0.0));
+    for y in 0..sheet.height as u32 {
+        for x in 0..sheet.width as u32 {
+            image.set_pixel(x, y, sheet.get_pixel(x, y));
+        }
+    }
 
-fn is_tile_in_grid(row:i32, col:i32, grid_rows:u8, grid_cols:u8) ->bool {
-    return row >= 0 && row < grid_rows as i32 && col >= 0 && col < grid_cols as i32;
+    let mut rects = Vec::new();
+    for (i, (source, count)) in sprites.iter().enumerate() {
+        let cell_x = i as u16 * (CELL + 1);
+        let cell_y = sheet.height + 1;
+        // back to front
+        let offsets:&[(u16, u16)] = if *count == 2 { &[(0, 0), (12, 12)] } else { &[(6, 0), (0, 12), (12, 12)] };
+        for &(dx, dy) in offsets {
+            for y in 0..SMALL {
+                for x in 0..SMALL {
+                    let source_x = (source.x + x as f32 * source.w / SMALL as f32) as u32;
+                    let source_y = (source.y + y as f32 * source.h / SMALL as f32) as u32;
+                    let color = sheet.get_pixel(source_x, source_y);
+                    if color.a > 0.5 {
+                        image.set_pixel((cell_x + dx + x) as u32, (cell_y + dy + y) as u32, color);
+                    }
+                }
+            }
+        }
+        rects.push(Rect::new(cell_x as f32, cell_y as f32, CELL as f32, CELL as f32));
+    }
+    (image, rects)
 }
 
-fn screen_to_tile_id(mouse_x:f32, mouse_y:f32, columns:i32, rows:i32, tile_size:f32)-> i32 {
-    let row:i32 = (mouse_y / tile_size) as i32;
-    let col:i32 = (mouse_x / tile_size) as i32;
-    if is_tile_in_grid(row, col, rows as u8, columns as u8) {
-        return row * columns + col;
-    }
-    else {
-        return -1;
+// width and height of the grid, in tile sizes. Hexagons are one tile size
+// across their flat sides, odd rows stick out half a tile to the right and
+// rows overlap by a quarter of a hexagon's height.
+fn grid_extent(grid:Grid) -> (f32, f32) {
+    // the ghost row and column of wrapping grids need room too
+    let rows = grid.rows + grid.wraps_rows() as u16;
+    let cols = grid.cols + grid.wraps_columns() as u16;
+    match grid.topology {
+        Topology::Square => (cols as f32, rows as f32),
+        Topology::Hex => {
+            let shift = if rows > 1 { 0.5 } else { 0.0 };
+            (cols as f32 + shift, (rows - 1) as f32 * HEX_ROW_PITCH + HEX_HEIGHT)
+        }
     }
 }
 
-fn draw_grid(arr: &[Tile], assets:&Assets, state:&GameState, rows:u8, columns:u8, max_tile_size:f32, x_offset:f32, y_offset:f32) {
-    let tile_size = calculate_tile_size(rows, columns, max_tile_size);
-
-    for i in 0..arr.len() {
-        let x:f32 = x_offset + (i as u8 % columns) as f32 * tile_size;
-        let y:f32 = y_offset + (i as u8 / columns) as f32 * tile_size;
-
-        if arr[i].revealed {
-            draw_rectangle(x, y, tile_size, tile_size, GRAY);
-            if arr[i].has_mine && (state == &GameState::GameRunning || state == &GameState::GameLost){
-                assets.draw(assets.explosion, x + tile_size / 2.0, y + tile_size / 2.0, tile_size);
-            }
+// zoom and pan over a board too big to read whole. At zoom 1 the whole board fits the screen.
+struct BoardCamera {
+    // the board it was set up for, showing another one starts over
+    grid:Option<Grid>,
+    zoom:f32,
+    // point of the grid in the middle of the view, in tile sizes from the grid's top left corner
+    center:(f32, f32),
+    // where the mouse was when a drag last moved the view
+    drag:Option<(f32, f32)>,
+}
 
-            else if arr[i].has_mine && state == &GameState::GameWon {
-                draw_rectangle(x, y, tile_size, tile_size, LIGHTGRAY);
-                assets.draw(assets.bomb, x + tile_size / 2.0, y + tile_size / 2.0, tile_size);
-            }
-            else {
+impl BoardCamera {
+    fn new() -> Self {
+        Self { grid: None, zoom: 1.0, center: (0.0, 0.0), drag: None }
+    }
 
-                match arr[i].adjacent_mines {
-                    1 => assets.draw(assets.one, x + tile_size / 2.0, y + tile_size / 2.0, tile_size),
-                    2 => assets.draw(assets.two, x + tile_size / 2.0, y + tile_size / 2.0, tile_size),
-                    3 => assets.draw(assets.three, x + tile_size / 2.0, y + tile_size / 2.0, tile_size),
-                    4 => assets.draw(assets.four, x + tile_size / 2.0, y + tile_size / 2.0, tile_size),
-                    5 => assets.draw(assets.five, x + tile_size / 2.0, y + tile_size / 2.0, tile_size),
-                    6 => assets.draw(assets.six, x + tile_size / 2.0, y + tile_size / 2.0, tile_size),
-                    7 => assets.draw(assets.seven, x + tile_size / 2.0, y + tile_size / 2.0, tile_size),
-                    8 => assets.draw(assets.eight, x + tile_size / 2.0, y + tile_size / 2.0, tile_size),
-                    _ => (),
-                }
+    // zooms all the way out when the board on screen changes shape
+    fn show(&mut self, grid:Grid) {
+        if self.grid != Some(grid) {
+            let (columns, rows) = grid_extent(grid);
+            *self = Self { grid: Some(grid), center: (columns / 2.0, rows / 2.0), ..Self::new() };
+        }
+    }
 
-            }
+    // the mouse wheel and +/- zoom, dragging with the middle button or with Shift held pans,
+    // and pressing the minimap jumps there. Returns true while a drag is using the mouse.
+    fn update(&mut self, layout:&ScreenLayout) -> bool {
+        let mouse = mouse_position();
+        let (_, wheel) = mouse_wheel();
+        let mut factor = 1.0;
+        if wheel != 0.0 && layout.area.contains(Vec2::from(mouse)) {
+            factor = if wheel > 0.0 { 1.25 } else { 0.8 };
+        } else if is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd) {
+            factor = 1.25;
+        } else if is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract) {
+            factor = 0.8;
         }
-        else {
-            draw_rectangle(x, y, tile_size, tile_size, LIGHTGRAY);
+        if factor != 1.0 {
+            // the wheel keeps the point under the pointer in place, the keys the middle of the view
+            let focus = if wheel != 0.0 { mouse } else { layout.area.center().into() };
+            let (x, y) = layout.to_grid(focus);
+            self.zoom = (self.zoom * factor).clamp(1.0, layout.max_zoom);
+            let tile_size = layout.fit_tile_size * self.zoom;
+            let area_center = layout.area.center();
+            self.center = (x - (focus.0 - area_center.x) / tile_size, y - (focus.1 - area_center.y) / tile_size);
         }
-        if arr[i].flagged {
-            if state == &GameState::GameRunning {
-                assets.draw(assets.flag, x + tile_size / 2.0, y + tile_size / 2.0, tile_size);
+
+        let dragging = is_mouse_button_down(MouseButton::Middle) || (shift_down() && is_mouse_button_down(MouseButton::Left));
+        if dragging {
+            if let Some((x, y)) = self.drag {
+                self.center.0 -= (mouse.0 - x) / layout.tile_size;
+                self.center.1 -= (mouse.1 - y) / layout.tile_size;
             }
-            else if state == &GameState::GameLost || state == &GameState::GameWon {
-                if arr[i].has_mine {
-                    draw_rectangle(x, y, tile_size, tile_size, LIGHTGRAY);
-                    assets.draw(assets.bomb, x + tile_size / 2.0, y + tile_size / 2.0, tile_size);
-                }
-                else {
-                    let offset = tile_size / 3.0;
-                    assets.draw(assets.flag, x + tile_size / 2.0, y + tile_size / 2.0, tile_size);
-                    draw_line(x + offset, y + offset , x + tile_size - offset, y + tile_size - offset, 6.0, BLACK);
-                    draw_line(x + tile_size - offset, y + offset , x + offset, y + tile_size - offset, 6.0, BLACK);
+            self.drag = Some(mouse);
+        } else {
+            self.drag = None;
+            if let Some(minimap) = layout.minimap.filter(|minimap| minimap.contains(Vec2::from(mouse))) {
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    let (columns, rows) = layout.extent;
+                    self.center = ((mouse.0 - minimap.x) / minimap.w * columns, (mouse.1 - minimap.y) / minimap.h * rows);
                 }
             }
         }
-        draw_rectangle_lines(x, y, tile_size, tile_size, 1.0,DARKGRAY);
+        self.clamp(layout);
+        dragging
+    }
+
+    // moves the view just enough to show a point of the grid, for the keyboard cursor
+    fn reveal_point(&mut self, (x, y):(f32, f32), layout:&ScreenLayout) {
+        // with a tile's room to spare around it
+        let keep = |center:f32, point:f32, view:f32| {
+            let reach = (view / 2.0 - 1.0).max(0.0);
+            center.clamp(point - reach, point + reach)
+        };
+        self.center = (keep(self.center.0, x, layout.area.w / layout.tile_size), keep(self.center.1, y, layout.area.h / layout.tile_size));
+        self.clamp(layout);
+    }
+
+    // keeps the view on the board, centring the board along a side it doesn't fill
+    fn clamp(&mut self, layout:&ScreenLayout) {
+        let tile_size = layout.fit_tile_size * self.zoom;
+        let (columns, rows) = layout.extent;
+        let fit = |center:f32, extent:f32, view:f32| {
+            if extent <= view { extent / 2.0 } else { center.clamp(view / 2.0, extent - view / 2.0) }
+        };
+        self.center = (fit(self.center.0, columns, layout.area.w / tile_size), fit(self.center.1, rows, layout.area.h / tile_size));
     }
 }
 
-fn flag_tile(arr: &mut [Tile], tile_id:usize, flags_remaning:u16)-> bool{
-    if !arr[tile_id].revealed {
-        if arr[tile_id].flagged {
-            arr[tile_id].flagged = false;
-            return true;
-        }
-        else if arr[tile_id].flagged == false && flags_remaning > 0 {
-            arr[tile_id].flagged = !arr[tile_id].flagged;
-            return true;
-        }
-        else {
-            return false;
+// where everything goes on screen this frame. It is worked out again every frame
+// from the window size and the camera, so resizing the window, going fullscreen,
+// zooming and panning reflow the grid, the top panel and the overlays together.
+struct ScreenLayout {
+    screen_w:f32,
+    screen_h:f32,
+    // menus grow and shrink with the window
+    scale_factor:f32,
+    // area left for the grid below the top panel
+    area:Rect,
+    // width and height of the board on screen, in tile sizes
+    extent:(f32, f32),
+    // tile size that fits the whole board, and how far the camera may zoom in from it
+    fit_tile_size:f32,
+    max_zoom:f32,
+    // tiles of the board on screen, and the top left corner of its grid
+    tile_size:f32,
+    x_offset:f32,
+    y_offset:f32,
+    // overview of the whole board in the top right corner, while zoomed in
+    minimap:Option<Rect>,
+    // the player's interface scale, and the top panel's height with it
+    ui_scale:f32,
+    panel_height:f32,
+    // text scales with the tiles, but stays readable on large boards
+    text_size:f32,
+}
+
+impl ScreenLayout {
+    fn new(grid:Grid, camera:&BoardCamera, ui_scale:f32) -> Self {
+        let (screen_w, screen_h) = (screen_width(), screen_height());
+        let scale_factor = (screen_w / WINDOW_WIDTH as f32).min(screen_h / WINDOW_HEIGHT as f32).clamp(0.5, 2.0) * ui_scale;
+        let panel_height = TOP_PANEL_HEIGHT * ui_scale;
+        let area = Rect::new(
+            GRID_MARGIN,
+            panel_height + GRID_MARGIN,
+            (screen_w - 2.0 * GRID_MARGIN).max(0.0),
+            (screen_h - panel_height - 2.0 * GRID_MARGIN).max(0.0),
+        );
+        // largest tile that fits the board in both directions, so wide boards are limited by the width
+        let (columns, rows) = grid_extent(grid);
+        let fit_tile_size = (area.w / columns).min(area.h / rows).clamp(1.0, MAX_TILE_SIZE);
+        let tile_size = fit_tile_size * camera.zoom;
+
+        let minimap = (camera.zoom > 1.0).then(|| {
+            let scale = 150.0 * scale_factor / columns.max(rows);
+            let (w, h) = (columns * scale, rows * scale);
+            Rect::new(area.right() - w - GRID_MARGIN, area.y + GRID_MARGIN, w, h)
+        });
+
+        Self {
+            screen_w,
+            screen_h,
+            scale_factor,
+            area,
+            extent: (columns, rows),
+            fit_tile_size,
+            max_zoom: (MAX_TILE_SIZE / fit_tile_size).max(1.0),
+            tile_size,
+            // the camera's centre goes in the middle of the area
+            x_offset: area.center().x - camera.center.0 * tile_size,
+            y_offset: area.center().y - camera.center.1 * tile_size,
+            minimap,
+            ui_scale,
+            panel_height,
+            text_size: fit_tile_size.max(panel_height),
         }
     }
 
-    else {
-        return false;
+    // point on the screen in tile sizes from the grid's top left corner
+    fn to_grid(&self, (x, y):(f32, f32)) -> (f32, f32) {
+        ((x - self.x_offset) / self.tile_size, (y - self.y_offset) / self.tile_size)
     }
-}
 
-fn reveal_tile(arr: &mut [Tile], tile_id:usize, rows:u8, cols:u8){
-    if !arr[tile_id].flagged {
-        arr[tile_id].revealed = true;
-        if arr[tile_id].adjacent_mines == 0 {
-            reveal_adjacent_tiles(arr, tile_id, rows, cols);
+    // tile under a point on the screen, for a board the layout was made for. Tiles
+    // zoomed out of the grid area, or under the minimap, can't be clicked.
+    fn tile_at(&self, board:&Board, point:(f32, f32)) -> Option<usize> {
+        let inside = self.area.contains(Vec2::from(point)) && !self.minimap.is_some_and(|minimap| minimap.contains(Vec2::from(point)));
+        if !inside {
+            return None;
         }
+        screen_to_tile_id(board, point.0 - self.x_offset, point.1 - self.y_offset, self.tile_size)
     }
 }
 
-fn reveal_adjacent_tiles(arr:&mut [Tile], tile_id:usize, rows:u8, cols:u8) {
-    if arr[tile_id].adjacent_mines == 0 && !arr[tile_id].has_mine {
-        for r in -1..2 {
-            for c in -1..2{
-                if r == 0 && c == 0 {
-                    continue;
-                }
-                else {
-                    let next_row:i32 = tile_id as i32 / cols as i32 + r;
-                    let next_col:i32 = tile_id as i32 % cols as i32 + c;
-                    if is_tile_in_grid(next_row, next_col, rows, cols)
-                    {
-                        let next_id = (next_row * cols as i32 + next_col) as usize;
-                        if arr[next_id].revealed ==false {
-                            arr[next_id].adjacent_mines = num_adjacent_mines(&arr, rows, cols, next_id);
-                            if !arr[next_id].has_mine {
-                                reveal_tile(arr, next_id, rows, cols);
-                            }
-                        }
-                    }
-                }
-            }
+// centre of a tile, relative to the grid offsets
+fn tile_center(grid:Grid, row:i32, col:i32, tile_size:f32) -> (f32, f32) {
+    match grid.topology {
+        Topology::Square => ((col as f32 + 0.5) * tile_size, (row as f32 + 0.5) * tile_size),
+        Topology::Hex => {
+            let shift = if row.rem_euclid(2) == 1 { 0.5 } else { 0.0 };
+            ((col as f32 + 0.5 + shift) * tile_size, (row as f32 * HEX_ROW_PITCH + HEX_HEIGHT / 2.0) * tile_size)
         }
-    } 
-
+    }
 }
 
-// chording action - both mouse buttons pressed on a revelealed tile whith a number equal to flagged adjacent cells
-fn reveal_all_adjacent_tiles(arr: &mut [Tile], tile_id:usize, rows:u8, cols:u8) {
-    // count flagged adjecent cells
-    let mut flagged_cells = 0;
+// centre of a tile in tile sizes from the grid's top left corner
+fn tile_position(grid:Grid, tile_id:usize) -> (f32, f32) {
+    let (row, col) = grid.position(tile_id);
+    tile_center(grid, row, col, 1.0)
+}
 
-    for r in -1..2 {
-        for c in -1..2{
-            if r == 0 && c == 0 {
-                continue;
-            }
-            else {
-                let next_row:i32 = tile_id as i32 / cols as i32 + r;
-                let next_col:i32 = tile_id as i32 % cols as i32 + c;
-                if is_tile_in_grid(next_row, next_col, rows, cols)
-                {
-                    let next_id = (next_row * cols as i32 + next_col) as usize;
-                    if arr[next_id].flagged { flagged_cells += 1; }
-                }
-            }
-        }
-        // check if number of flagged adjacent cells = number of adjecent mines
-        if flagged_cells == arr[tile_id].adjacent_mines {
-            for r in -1..2 {
-                for c in -1..2{
-                    if r == 0 && c == 0 {
-                        continue;
-                    }
-                    else {
-                        let next_row:i32 = tile_id as i32 / cols as i32 + r;
-                        let next_col:i32 = tile_id as i32 % cols as i32 + c;
-                        if is_tile_in_grid(next_row, next_col, rows, cols)
-                        {
-                            let next_id = (next_row * cols as i32 + next_col) as usize;
-                            arr[next_id].adjacent_mines = num_adjacent_mines(&arr, rows, cols, next_id);
-                            reveal_tile(arr, next_id, rows, cols);
-                        }
-                    }
-                }
-            }
+// tile under a point given relative to the grid offsets
+fn screen_to_tile_id(board:&Board, mouse_x:f32, mouse_y:f32, tile_size:f32) -> Option<usize> {
+    if mouse_x < 0.0 || mouse_y < 0.0 {
+        return None;
+    }
+    let grid = board.grid();
+    match grid.topology {
+        Topology::Square => board.tile_id((mouse_y / tile_size) as i32, (mouse_x / tile_size) as i32),
+        Topology::Hex => {
+            // a point lies in the hexagon whose centre is closest, which is one of the
+            // tiles around the row and column it would be in on a square grid
+            let row = (mouse_y / (HEX_ROW_PITCH * tile_size)) as i32;
+            let col = (mouse_x / tile_size) as i32;
+            let distance = |(r, c):(i32, i32)| {
+                let (x, y) = tile_center(grid, r, c, tile_size);
+                (x - mouse_x).powi(2) + (y - mouse_y).powi(2)
+            };
+            let (row, col) = (row - 1..=row + 1)
+                .flat_map(|r| (col - 1..=col + 1).map(move |c| (r, c)))
+                .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))?;
+            board.tile_id(row, col)
         }
-    } 
+    }
 }
 
-fn num_adjacent_mines(arr: &[Tile], grid_rows:u8, grid_cols:u8, tile_id:usize)->u8{
-    let mut mines:u8 = 0;
-    let row:i32 = tile_id as i32 / grid_cols as i32;
-    let col:i32 = tile_id as i32 % grid_cols as i32;
+// tile shapes, drawn around their centre
+fn fill_tile(grid:Grid, x:f32, y:f32, tile_size:f32, color:Color) {
+    match grid.topology {
+        Topology::Square => draw_rectangle(x - tile_size / 2.0, y - tile_size / 2.0, tile_size, tile_size, color),
+        Topology::Hex => draw_poly(x, y, 6, tile_size * HEX_HEIGHT / 2.0, 90.0, color),
+    }
+}
 
-    for r in -1..2 {
-        for c in -1..2{
-            if r == 0 && c == 0 {
-                continue;
-            }
-            else {
-                let next_row:i32 = row + r;
-                let next_col:i32 = col + c;
-                let next_id = (next_row * grid_cols as i32 + next_col) as usize;
-    
-                if is_tile_in_grid(next_row, next_col, grid_rows, grid_cols) {
-                    if arr[next_id].has_mine {
-                        mines += 1;
-                    }
-             
-                }
-            }
-        }
+fn outline_tile(grid:Grid, x:f32, y:f32, tile_size:f32, thickness:f32, color:Color) {
+    match grid.topology {
+        Topology::Square => draw_rectangle_lines(x - tile_size / 2.0, y - tile_size / 2.0, tile_size, tile_size, thickness, color),
+        Topology::Hex => draw_poly_lines(x, y, 6, tile_size * HEX_HEIGHT / 2.0, 90.0, thickness, color),
     }
-    return mines;
 }
 
-fn initialize_grid(arr: &mut[Tile], num_tiles:u16) {
-    //srand(get_time() as u64);
-    for i in 0..num_tiles {
-        arr[i as usize] = Tile { revealed: false, has_mine: false, flagged: false, adjacent_mines:0 };
+// three diagonal lines across the middle of a tile, inside a circle that fits either shape
+fn hatch_tile(x:f32, y:f32, tile_size:f32, color:Color) {
+    let radius = tile_size * 0.35;
+    let thickness = (tile_size / 20.0).max(1.0);
+    let color = Color { a: color.a * 0.6, ..color };
+    for offset in [-0.5, 0.0, 0.5] {
+        // lines running down to the right, spaced along the other diagonal
+        let offset = offset * radius;
+        let half_length = (radius * radius - offset * offset).sqrt() / std::f32::consts::SQRT_2;
+        let (cx, cy) = (x + offset / std::f32::consts::SQRT_2, y - offset / std::f32::consts::SQRT_2);
+        draw_line(cx - half_length, cy - half_length, cx + half_length, cy + half_length, thickness, color);
     }
 }
 
-fn place_mines(arr: &mut[Tile], num_mines:u16, num_tiles:u16, exclude_tile_id:usize) {
-    let mut placed_mines:u16 = 0;
+// extra information drawn on top of the tiles
+#[derive(Default)]
+struct GridOverlay<'a> {
+    hint:Option<usize>,
+    heatmap:Option<&'a [f64]>,
+    // keyboard cursor
+    cursor:Option<usize>,
+}
+
+// one tile drawn around its centre, which for the ghosts of a wrapping grid is off the board
+fn draw_tile(board:&Board, assets:&Assets, state:&GameState, tile_id:usize, (x, y):(f32, f32), tile_size:f32, heatmap:Option<&[f64]>) {
+    let grid = board.grid();
+    let tile = board.tile(tile_id);
+    let colors = &assets.colors;
 
-    while placed_mines < num_mines {
-        let n:usize = gen_range(0, num_tiles as usize);
-        if arr[n].has_mine == true || n == exclude_tile_id {
-            continue;
+    if tile.revealed {
+        fill_tile(grid, x, y, tile_size, colors.revealed);
+        if tile.has_mine() && (state == &GameState::GameRunning || state == &GameState::GameLost){
+            assets.draw(assets.explosion, x, y, tile_size);
+        }
+
+        // the editor shows its mines like a won game
+        else if tile.has_mine() && (state == &GameState::GameWon || state == &GameState::Editor) {
+            fill_tile(grid, x, y, tile_size, colors.mine);
+            assets.draw(assets.bombs(tile.mines), x, y, tile_size);
         }
         else {
-            arr[n].has_mine = true;
-            placed_mines +=1;
+
+            match tile.adjacent_mines {
+                n @ 1..=8 if assets.text_numbers => assets.draw_number(n, x, y, tile_size),
+                1 => assets.draw(assets.one, x, y, tile_size),
+                2 => assets.draw(assets.two, x, y, tile_size),
+                3 => assets.draw(assets.three, x, y, tile_size),
+                4 => assets.draw(assets.four, x, y, tile_size),
+                5 => assets.draw(assets.five, x, y, tile_size),
+                6 => assets.draw(assets.six, x, y, tile_size),
+                7 => assets.draw(assets.seven, x, y, tile_size),
+                8 => assets.draw(assets.eight, x, y, tile_size),
+                n @ 9.. => assets.draw_number(n, x, y, tile_size),
+                _ => (),
+            }
+
+        }
+    }
+    else {
+        fill_tile(grid, x, y, tile_size, colors.hidden);
+        // tint from green (safe) to red (mine), or the colour blind palette's ends, by the chance of a mine
+        if let Some(probabilities) = heatmap {
+            let p = probabilities[tile_id] as f32;
+            let (safe, mine) = assets.heatmap;
+            let mix = |from:f32, to:f32| from + (to - from) * p;
+            fill_tile(grid, x, y, tile_size, Color::new(mix(safe.r, mine.r), mix(safe.g, mine.g), mix(safe.b, mine.b), 0.45));
+        }
+        if assets.patterned_tiles {
+            hatch_tile(x, y, tile_size, colors.outline);
+        }
+    }
+    if tile.flagged() {
+        if state == &GameState::GameRunning {
+            assets.draw(assets.flags(tile.flags), x, y, tile_size);
+        }
+        else if state == &GameState::GameLost || state == &GameState::GameWon {
+            if tile.has_mine() {
+                fill_tile(grid, x, y, tile_size, colors.mine);
+                assets.draw(assets.bombs(tile.mines), x, y, tile_size);
+            }
+            else {
+                let offset = tile_size / 2.0 - tile_size / 3.0;
+                assets.draw(assets.flags(tile.flags), x, y, tile_size);
+                draw_line(x - offset, y - offset, x + offset, y + offset, (tile_size / 10.0).max(1.0), colors.wrong_flag);
+                draw_line(x + offset, y - offset, x - offset, y + offset, (tile_size / 10.0).max(1.0), colors.wrong_flag);
+            }
         }
     }
+    let thickness = if assets.bold_outlines { (tile_size / 12.0).max(2.0) } else { 1.0 };
+    outline_tile(grid, x, y, tile_size, thickness, colors.outline);
 }
 
-fn update_game_state(arr: &[Tile], state: &mut GameState, num_mines:u16) {
-    let mut revealed_tiles = 0;
-    let mut flagged_mines = 0;
+fn draw_grid(board:&Board, assets:&Assets, state:&GameState, layout:&ScreenLayout, overlay:&GridOverlay) {
+    let grid = board.grid();
+    let ScreenLayout { tile_size, x_offset, y_offset, .. } = *layout;
+    let center = |tile_id:usize| {
+        let (row, col) = grid.position(tile_id);
+        let (x, y) = tile_center(grid, row, col, tile_size);
+        (x_offset + x, y_offset + y)
+    };
+    // zoomed in, only the tiles in or touching the grid area are drawn
+    let area = layout.area;
+    let visible = |(x, y):(f32, f32)| {
+        x > area.left() - tile_size && x < area.right() + tile_size && y > area.top() - tile_size && y < area.bottom() + tile_size
+    };
+
+    for tile_id in 0..board.tile_count() {
+        let center = center(tile_id);
+        if visible(center) {
+            draw_tile(board, assets, state, tile_id, center, tile_size, overlay.heatmap);
+        }
+    }
 
-    for tile in arr {
-        if *state == GameState::GameRunning {
-            if tile.revealed && tile.has_mine {
-                *state = GameState::GameLost;
-                return;
-            }
-            if tile.revealed && !tile.has_mine {
-                revealed_tiles += 1;
-            }
-            if tile.flagged && tile.has_mine {
-                flagged_mines += 1;
-            }
+    // wrapping grids repeat their first column and row faintly past the edges
+    let (rows, columns) = (grid.rows as i32, grid.cols as i32);
+    let mut ghosts = Vec::new();
+    if grid.wraps_columns() {
+        ghosts.extend((0..rows).map(|row| (row, 0, row, columns)));
+    }
+    if grid.wraps_rows() {
+        ghosts.extend((0..columns).map(|col| (0, col, rows, col)));
+    }
+    if grid.wraps_columns() && grid.wraps_rows() {
+        ghosts.push((0, 0, rows, columns));
+    }
+    for (row, col, ghost_row, ghost_col) in ghosts {
+        let (x, y) = tile_center(grid, ghost_row, ghost_col, tile_size);
+        let (x, y) = (x_offset + x, y_offset + y);
+        if visible((x, y)) {
+            draw_tile(board, assets, state, (row * columns + col) as usize, (x, y), tile_size, overlay.heatmap);
+            fill_tile(grid, x, y, tile_size, Color { a: 0.6, ..assets.colors.background });
         }
+    }
+
+    // outline the tile suggested by the hint key
+    if let Some(tile_id) = overlay.hint {
+        let (x, y) = center(tile_id);
+        outline_tile(grid, x, y, tile_size, (tile_size / 8.0).max(2.0), assets.colors.hint);
+    }
+
+    if let Some(tile_id) = overlay.cursor {
+        let (x, y) = center(tile_id);
+        outline_tile(grid, x, y, tile_size, (tile_size / 8.0).max(2.0), assets.colors.cursor);
+    }
 
+    if let Some(minimap) = layout.minimap {
+        draw_minimap(board, &assets.colors, layout, minimap);
     }
+}
 
-    if revealed_tiles > 0 && revealed_tiles == arr.len() - num_mines as usize {
-        *state = GameState::GameWon;
-    } else if flagged_mines == num_mines {
-        *state = GameState::GameWon;
+// the whole board in miniature, a block of tiles per dot, with the part on screen outlined
+fn draw_minimap(board:&Board, colors:&Palette, layout:&ScreenLayout, minimap:Rect) {
+    const DOTS:usize = 60;
+    let grid = board.grid();
+    let (columns, rows) = layout.extent;
+    let scale = minimap.w / columns;
+    draw_rectangle(minimap.x, minimap.y, minimap.w, minimap.h, Color { a: 0.85, ..colors.background });
+
+    // blocks of tiles with most of them revealed show as revealed
+    let block = (grid.rows.max(grid.cols) as usize).div_ceil(DOTS);
+    let row_pitch = if grid.topology == Topology::Hex { HEX_ROW_PITCH } else { 1.0 };
+    for block_row in (0..grid.rows as usize).step_by(block) {
+        for block_col in (0..grid.cols as usize).step_by(block) {
+            let rows = block_row..(block_row + block).min(grid.rows as usize);
+            let cols = block_col..(block_col + block).min(grid.cols as usize);
+            let tiles = rows.len() * cols.len();
+            let revealed = rows
+                .clone()
+                .flat_map(|row| cols.clone().map(move |col| row * grid.cols as usize + col))
+                .filter(|&tile_id| board.tile(tile_id).revealed)
+                .count();
+            let color = if revealed * 2 > tiles { colors.revealed } else { colors.hidden };
+            let x = minimap.x + block_col as f32 * scale;
+            let y = minimap.y + block_row as f32 * row_pitch * scale;
+            draw_rectangle(x, y, cols.len() as f32 * scale, rows.len() as f32 * row_pitch * scale, color);
+        }
+    }
+
+    // the grid area, in the minimap's scale
+    let (left, top) = layout.to_grid((layout.area.x, layout.area.y));
+    let (right, bottom) = layout.to_grid((layout.area.right(), layout.area.bottom()));
+    let (left, right) = (left.max(0.0), right.min(columns));
+    let (top, bottom) = (top.max(0.0), bottom.min(rows));
+    draw_rectangle_lines(minimap.x + left * scale, minimap.y + top * scale, (right - left) * scale, (bottom - top) * scale, 2.0, colors.cursor);
+    draw_rectangle_lines(minimap.x, minimap.y, minimap.w, minimap.h, 1.0, colors.outline);
+}
+
+// arrow keys or WASD move the keyboard cursor, which starts in the middle of the board
+fn move_cursor(board:&Board, cursor:Option<usize>) -> Option<usize> {
+    let (d_row, d_col) = if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
+        (-1, 0)
+    } else if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
+        (1, 0)
+    } else if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A) {
+        (0, -1)
+    } else if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D) {
+        (0, 1)
+    } else {
+        return cursor;
+    };
+    let columns = board.columns() as i32;
+    match cursor {
+        None => board.tile_id(board.rows() as i32 / 2, columns / 2),
+        // stay on the board at the edges
+        Some(tile_id) => board.tile_id(tile_id as i32 / columns + d_row, tile_id as i32 % columns + d_col).or(cursor),
     }
 }
 
@@ -403,15 +999,19 @@ fn window_conf() -> Conf {
         window_width: WINDOW_WIDTH,
         window_height: WINDOW_HEIGHT,
         high_dpi: true,
-        window_resizable: false,
+        window_resizable: true,
         fullscreen: false,
         ..Default::default()
     }
 }
 
-fn draw_top_ui_panel(screen_w:f32, font:&Font, mines_flagged:u16, mines:u16, level_time:f64, tile_height:f32) {
+fn draw_top_ui_panel(layout:&ScreenLayout, assets:&Assets, board:&Board, level_time:f64) {
+    let (font, colors) = (&assets.font, &assets.colors);
+    let screen_w = layout.screen_w;
     let menu_width = screen_w / 1.5;
-    let menu_height = tile_height;
+    let menu_height = layout.panel_height;
+    // covers tiles of a zoomed in board
+    draw_rectangle(0.0, 0.0, screen_w, layout.panel_height, colors.background);
     let menu_x = (screen_w - menu_width) / 2.0;
     let menu_y = 10.0;
 
@@ -419,315 +1019,1341 @@ fn draw_top_ui_panel(screen_w:f32, font:&Font, mines_flagged:u16, mines:u16, lev
     // let menu_col:Color = Color::new(0.2, 0.2, 0.2, 0.5);
     // draw_rectangle(menu_x, menu_y, menu_width, menu_height, menu_col);
 
-    // Calculate the position of the text to be centered in the menu
-    let text = &format!("MINES FLAGGED: {} of {} | TIME: {:.0}", mines_flagged, mines, level_time);
-    let font_size = (tile_height * 0.7).min(20.0) as u16;
-    let text_dimensions = measure_text(text, Some(&font), font_size, 1.0);
-    let text_x = menu_x + (menu_width - text_dimensions.width) / 2.0;
-    let text_y = menu_y + (menu_height - text_dimensions.height) / 2.0 + text_dimensions.offset_y;
+    let clicks = board.clicks();
+    let lines = [
+        format!(
+            "{}MINES FLAGGED: {} of {} | TIME: {:.0} | SEED: {}",
+            if board.practice() { "PRACTICE | " } else { "" },
+            board.mines_flagged(),
+            board.mines(),
+            level_time,
+            board.seed()
+        ),
+        format!(
+            "3BV: {}/{} | 3BV/s: {:.2} | IOE: {} | CLICKS: {}L {}R {}C",
+            board.solved_three_bv(),
+            board.three_bv().map_or("-".to_owned(), |three_bv| three_bv.to_string()),
+            board.three_bv_per_second(level_time),
+            board.efficiency().map_or("-".to_owned(), |ioe| format!("{:.2}", ioe)),
+            clicks.left,
+            clicks.right,
+            clicks.chord
+        ),
+    ];
+    let line_height = menu_height / lines.len() as f32;
+
+    for (i, text) in lines.iter().enumerate() {
+        // Calculate the position of the text to be centered in the menu, shrinking it to fit the screen
+        let mut font_size = (line_height * 0.8).min(20.0 * layout.ui_scale) as u16;
+        let full_width = measure_text(text, Some(font), font_size, 1.0).width;
+        if full_width > screen_w - 20.0 {
+            font_size = (font_size as f32 * (screen_w - 20.0) / full_width) as u16;
+        }
+        let text_dimensions = measure_text(text, Some(font), font_size, 1.0);
+        let text_x = menu_x + (menu_width - text_dimensions.width) / 2.0;
+        let text_y = menu_y + i as f32 * line_height + (line_height - text_dimensions.height) / 2.0 + text_dimensions.offset_y;
+
+        // Draw the text
+        draw_text_ex(
+            text,
+            text_x,
+            text_y,
+            TextParams {
+                font: Some(font),
+                font_size,
+                // practice games are marked in red so they aren't mistaken for ranked ones
+                color: if i == 0 && board.practice() { colors.alert } else { colors.text },
+                ..Default::default()
+            },
+        );
+    }
+}
+
+// dimmed box in the middle of the screen with a title and a few lines of text below it
+fn draw_end_overlay(assets:&Assets, title:&str, title_color:Color, lines:&[String], layout:&ScreenLayout) {
+    let (font, colors) = (&assets.font, &assets.colors);
+    let screen_width = layout.screen_w;
+    let screen_height = layout.screen_h;
+    let tile_size = layout.text_size;
+    let line_spacing = 20.0 * layout.ui_scale;
+    let margin = 5.0;
+
+    let font1_size = (tile_size / 1.5) as u16;
+    let title_size = measure_text(title, Some(font), font1_size, 1.0);
+    let title_y = screen_height / 2.0 - title_size.height / 2.0;
+
+    let font2_size = (tile_size / 2.0).min(24.0 * layout.ui_scale) as u16;
+    let mut line_positions = Vec::with_capacity(lines.len());
+    let mut width = title_size.width;
+    let mut y = title_y;
+    for line in lines {
+        let line_size = measure_text(line, Some(font), font2_size, 1.0);
+        y += line_spacing + line_size.height;
+        width = width.max(line_size.width);
+        line_positions.push((screen_width / 2.0 - line_size.width / 2.0, y));
+    }
+
+    let rect_y = title_y - title_size.height - margin;
+    draw_rectangle(screen_width / 2.0 - width / 2.0 - margin, rect_y, width + margin + margin, y + margin - rect_y, colors.overlay);
 
-    // Draw the text
+    draw_text_ex(
+        title,
+        screen_width / 2.0 - title_size.width / 2.0,
+        title_y,
+        TextParams {
+            font: Some(font),
+            font_size: font1_size,
+            color: title_color,
+            ..Default::default()
+        }
+    );
+
+    for (line, (x, y)) in lines.iter().zip(line_positions) {
+        draw_text_ex(
+            line,
+            x,
+            y,
+            TextParams {
+                font: Some(font),
+                font_size: font2_size,
+                color: colors.overlay_text,
+                ..Default::default()
+            }
+        );
+    }
+}
+
+// short notice along the bottom edge of the screen
+fn draw_status_message(assets:&Assets, text:&str, layout:&ScreenLayout) {
+    let (font, colors) = (&assets.font, &assets.colors);
+    let font_size = (layout.text_size * 0.5).clamp(12.0, 24.0 * layout.ui_scale) as u16;
+    let text_dimensions = measure_text(text, Some(font), font_size, 1.0);
     draw_text_ex(
         text,
-        text_x,
-        text_y,
+        layout.screen_w / 2.0 - text_dimensions.width / 2.0,
+        layout.screen_h - text_dimensions.height,
         TextParams {
             font: Some(font),
-            font_size: font_size,
-            color: ORANGE,
+            font_size,
+            color: colors.text,
             ..Default::default()
-        },
+        }
     );
+}
+
+// menu title at the top of the screen, returning where the buttons may start
+fn draw_menu_title(assets:&Assets, title:&str, layout:&ScreenLayout) -> f32 {
+    let (font, colors) = (&assets.font, &assets.colors);
+    let scale_factor = layout.scale_factor;
+    let title_padding = 20.0 * scale_factor;
+    let title_size = 50.0 * scale_factor;
+    let title_dimensions = measure_text(title, Some(font), title_size as u16, 1.0);
+    let title_x = layout.screen_w / 2.0 - title_dimensions.width / 2.0;
+    let title_y = title_dimensions.height + title_padding;
+    draw_text_ex(
+        title,
+        title_x,
+        title_y,
+        TextParams {
+            font: Some(font),
+            font_size: title_size as u16,
+            color: colors.text,
+            ..Default::default()
+        }
+    );
+    title_y + title_padding
+}
+
+// centered column of buttons, highlighted in the given color if any, returning the index of the clicked one.
+// Up/Down move the keyboard focus between the buttons and Enter clicks the focused one.
+fn draw_menu_buttons(assets:&Assets, buttons:&[(&str, Option<Color>)], menu_top:f32, layout:&ScreenLayout, focus:&mut Option<usize>) -> Option<usize> {
+    let (font, colors) = (&assets.font, &assets.colors);
+    let scale_factor = layout.scale_factor;
+
+    let count = buttons.len();
+    if is_key_pressed(KeyCode::Down) {
+        *focus = Some(focus.map_or(0, |i| (i + 1) % count));
+    }
+    if is_key_pressed(KeyCode::Up) {
+        *focus = Some(focus.map_or(count - 1, |i| (i + count - 1) % count));
+    }
+    // the buttons can change from frame to frame
+    *focus = focus.map(|i| i.min(count - 1));
+
+    let mut clicked = None;
+    for (i, ((label, highlight), rect)) in buttons.iter().zip(menu_button_rects(count, menu_top, layout)).enumerate() {
+        let Rect { x, y, w: button_width, h: button_height } = rect;
+
+        // Determine button color
+        let mouse_position = mouse_position();
+        let is_hovered = mouse_position.0 >= x && mouse_position.0 <= x + button_width &&
+                         mouse_position.1 >= y && mouse_position.1 <= y + button_height;
+        let is_focused = *focus == Some(i);
+        let color = match highlight {
+            Some(color) => *color,
+            None if is_hovered || is_focused => colors.button_hover,
+            None => colors.button,
+        };
+
+        // Draw button background
+        draw_rectangle(x, y, button_width, button_height, color);
+        if is_focused {
+            draw_rectangle_lines(x, y, button_width, button_height, 4.0 * scale_factor, colors.cursor);
+        }
+
+        // Draw button label
+        let mut label_size = 0.6 * button_height;
+        let mut label_dimensions = measure_text(label, Some(font), label_size as u16, 1.0);
+        // long labels shrink to fit the button
+        if label_dimensions.width > 0.9 * button_width {
+            label_size *= 0.9 * button_width / label_dimensions.width;
+            label_dimensions = measure_text(label, Some(font), label_size as u16, 1.0);
+        }
+        draw_text_ex(
+            label,
+            x + button_width / 2.0 - label_dimensions.width / 2.0,
+            y + button_height / 2.0 + label_dimensions.height / 4.0,
+            TextParams {
+                font: Some(font),
+                font_size: label_size as u16,
+                color: colors.text,
+                ..Default::default()
+            }
+        );
+
+        // Check for mouse click
+        if (is_mouse_button_pressed(MouseButton::Left) && is_hovered) || (is_focused && is_key_pressed(KeyCode::Enter)) {
+            clicked = Some(i);
+        }
+    }
+    clicked
+}
+
+// where `draw_menu_buttons` puts a column of `count` buttons
+fn menu_button_rects(count:usize, menu_top:f32, layout:&ScreenLayout) -> Vec<Rect> {
+    let scale_factor = layout.scale_factor;
+    // shrink the buttons when they would not fit below the title
+    let button_scale = ((layout.screen_h - 2.0 * menu_top) / (count as f32 * 84.0 * scale_factor)).min(1.0);
+    let button_height = 60.0 * scale_factor * button_scale;
+    let button_width = 300.0 * scale_factor;
+    let button_spacing = 24.0 * scale_factor * button_scale;
+    let total_height = count as f32 * (button_height + button_spacing) - button_spacing;
+    (0..count)
+        .map(|i| {
+            let y = layout.screen_h / 2.0 - total_height / 2.0 + i as f32 * (button_height + button_spacing);
+            Rect::new(layout.screen_w / 2.0 - button_width / 2.0, y, button_width, button_height)
+        })
+        .collect()
+}
+
+// track along the bottom of a menu button, filled up to `value` from 0 to 1
+fn draw_slider(assets:&Assets, button:Rect, value:f32, layout:&ScreenLayout) {
+    let colors = &assets.colors;
+    let margin = 12.0 * layout.scale_factor;
+    let (x, y, w) = (button.x + margin, button.bottom() - button.h / 6.0, button.w - 2.0 * margin);
+    let thickness = (button.h / 15.0).max(2.0);
+    draw_line(x, y, x + w, y, thickness, colors.text_dim);
+    draw_line(x, y, x + w * value, y, thickness, colors.text);
+    draw_circle(x + w * value, y, thickness * 1.5, colors.text);
+}
+
+// row of buttons across the top panel, returning the one clicked
+fn draw_panel_buttons(assets:&Assets, labels:&[&str], layout:&ScreenLayout) -> Option<usize> {
+    let (font, colors) = (&assets.font, &assets.colors);
+    let screen_w = layout.screen_w;
+    draw_rectangle(0.0, 0.0, screen_w, layout.panel_height, colors.background);
+    let spacing = 8.0;
+    let button_height = layout.panel_height - 10.0;
+    let button_width = ((screen_w - 20.0) / labels.len() as f32 - spacing).min(130.0);
+    let total_width = labels.len() as f32 * (button_width + spacing) - spacing;
+
+    let mut clicked = None;
+    for (i, label) in labels.iter().enumerate() {
+        let x = screen_w / 2.0 - total_width / 2.0 + i as f32 * (button_width + spacing);
+        let y = 5.0;
+
+        let (mouse_x, mouse_y) = mouse_position();
+        let is_hovered = mouse_x >= x && mouse_x <= x + button_width && mouse_y >= y && mouse_y <= y + button_height;
+        draw_rectangle(x, y, button_width, button_height, if is_hovered { colors.button_hover } else { colors.button });
+
+        // shrink the label to fit the button
+        let mut font_size = (20.0 * layout.ui_scale) as u16;
+        let full_width = measure_text(label, Some(font), font_size, 1.0).width;
+        if full_width > button_width - 8.0 {
+            font_size = (font_size as f32 * (button_width - 8.0) / full_width) as u16;
+        }
+        let label_dimensions = measure_text(label, Some(font), font_size, 1.0);
+        draw_text_ex(
+            label,
+            x + button_width / 2.0 - label_dimensions.width / 2.0,
+            y + button_height / 2.0 + label_dimensions.height / 4.0,
+            TextParams {
+                font: Some(font),
+                font_size,
+                color: colors.text,
+                ..Default::default()
+            }
+        );
+
+        if is_mouse_button_pressed(MouseButton::Left) && is_hovered {
+            clicked = Some(i);
+        }
+    }
+    clicked
+}
+
+// plays the edited board as it stands
+fn test_game(editor:&Editor) -> Game {
+    let mut game = Game::from_board(Board::from_layout(&editor.layout(), editor.board().options()));
+    game.test_play = true;
+    game
+}
+
+// writes the edited board for "Load board", returning what to tell the player
+// and the tile the board can be cleared from without guessing
+fn save_edited_board(editor:&Editor) -> (String, Option<usize>) {
+    let start = editor.guess_free_start();
+    let verdict = match start {
+        Some(tile_id) => {
+            let (row, col) = editor.board().grid().position(tile_id);
+            format!("no guessing needed from row {}, column {}", row + 1, col + 1)
+        }
+        None => "it needs guessing".to_owned(),
+    };
+    let message = match storage::save(LAYOUT_KEY, &editor.layout().to_text()) {
+        Ok(()) => format!("Saved to {}, {}", LAYOUT_KEY, verdict),
+        Err(err) => format!("Could not save the board: {}", err),
+    };
+    (message, start)
+}
+
+// reads the board a player put in the data directory
+fn load_layout() -> Result<Layout, String> {
+    let text = storage::load(LAYOUT_KEY).ok_or(format!("No {} found next to the save", LAYOUT_KEY))?;
+    let layout = Layout::from_text(&text).map_err(|err| err.to_string())?;
+    if layout.rows > MAX_CUSTOM_SIZE || layout.columns > MAX_CUSTOM_SIZE {
+        return Err(format!("Boards can be at most {} x {}", MAX_CUSTOM_SIZE, MAX_CUSTOM_SIZE));
+    }
+    Ok(layout)
+}
+
+// writes the mines of the board in both formats, returning what to tell the player
+fn export_layout(board:&Board) -> String {
+    let Some(layout) = Layout::from_board(board) else {
+        return if board.mines_placed() { "Multi-mine boards can't be exported" } else { "No mines to export before the first reveal" }.to_owned();
+    };
+    match storage::save(EXPORT_KEY, &layout.to_text()).and_then(|_| storage::save(EXPORT_RAW_KEY, &layout.to_raw())) {
+        Ok(()) => format!("Board exported to {} and {}", EXPORT_KEY, EXPORT_RAW_KEY),
+        Err(err) => format!("Could not export the board: {}", err),
+    }
+}
+
+// keeps an unfinished game for the "Continue" button, or drops a finished one
+fn write_save(game:&Game, state:&GameState, elapsed:f64) {
+    if *state == GameState::GameRunning && game.board.mines_placed() {
+        let saved = SavedGame { board: game.board.clone(), elapsed };
+        if let Err(err) = storage::save(SAVE_KEY, &saved.to_text()) {
+            eprintln!("Could not save the game: {}", err);
+        }
+        match &game.recording {
+            Some(recording) => { let _ = storage::save(SAVE_REPLAY_KEY, &recording.to_text()); }
+            None => storage::remove(SAVE_REPLAY_KEY),
+        }
+    } else {
+        storage::remove(SAVE_KEY);
+        storage::remove(SAVE_REPLAY_KEY);
+    }
+}
+
+// adds a finished game to the stats store
+fn record_game(stats:&mut Stats, board:&Board, elapsed:f64) {
+    stats.push(GameRecord {
+        date: date_from_unix(macroquad::miniquad::date::now()),
+        level: level_name(board).to_owned(),
+        rows: board.rows(),
+        columns: board.columns(),
+        mines: board.mines(),
+        won: board.state() == BoardState::Won,
+        seconds: elapsed,
+        three_bv: board.three_bv().unwrap_or(0),
+        clicks: board.clicks().total(),
+        hints: board.hints_used(),
+        seed: board.seed(),
+        practice: board.practice(),
+    });
+    if let Err(err) = storage::save(STATS_KEY, &stats.to_text()) {
+        eprintln!("Could not save the statistics: {}", err);
+    }
+}
+
+// win rates and streaks for every level, and the fastest wins of one of them
+fn draw_stats_screen(assets:&Assets, stats:Option<&Stats>, level:&str, top:f32, layout:&ScreenLayout) {
+    let (font, colors) = (&assets.font, &assets.colors);
+    let scale_factor = layout.scale_factor;
+    let font_size = (18.0 * scale_factor) as u16;
+    let line_height = 24.0 * scale_factor;
+    let x = 40.0 * scale_factor;
+    let mut y = top + line_height;
+    let mut line = |text:&str, color:Color| {
+        draw_text_ex(text, x, y, TextParams { font: Some(font), font_size, color, ..Default::default() });
+        y += line_height;
+    };
+
+    let Some(stats) = stats else {
+        line(&format!("Could not read {}, games are not being recorded", STATS_KEY), colors.alert);
+        return;
+    };
+
+    let all = stats.summary(None);
+    line(&format!("Played {}, won {} ({:.0}%)", all.played, all.won, all.win_rate() * 100.0), colors.text);
+    line(&format!("Win streak {}, best streak {}", all.current_streak, all.best_streak), colors.text);
+    for name in LEVELS.iter().map(|(name, _)| *name).chain(["Custom"]) {
+        let summary = stats.summary(Some(name));
+        let best = summary.best_time.map_or("-".to_owned(), |time| format!("{:.2}s", time));
+        line(&format!("{}: {} played, {:.0}% won, best {}", name, summary.played, summary.win_rate() * 100.0, best), colors.text_dim);
+    }
+
+    line("", colors.text);
+    line(&format!("< Top {} {} >", LEADERBOARD_SIZE, level), colors.text);
+    let leaderboard = stats.leaderboard(level, LEADERBOARD_SIZE);
+    if leaderboard.is_empty() {
+        line("No wins yet", colors.text_dim);
+    }
+    for (rank, game) in leaderboard.iter().enumerate() {
+        line(
+            &format!("{:>2}. {:>8.2}s  3BV {:>3}  clicks {:>3}  {}", rank + 1, game.seconds, game.three_bv, game.clicks, game.date),
+            colors.text_dim,
+        );
+    }
+}
+
+// Shift, not Space, so a pan doesn't also reveal or toggle the tile under the keyboard cursor
+fn shift_down() -> bool {
+    is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift)
+}
+
+fn revealed_count(board:&Board) -> usize {
+    board.tiles().iter().filter(|tile| tile.revealed).count()
+}
+
+// why the settings couldn't be saved, if they couldn't
+fn save_settings(settings:&Settings) -> Option<String> {
+    storage::save(SETTINGS_KEY, &settings.to_text()).err().map(|err| format!("Could not save the settings: {}", err))
+}
+
+// the built-in themes followed by the player's own, with why it couldn't be read
+fn load_themes() -> (Vec<Theme>, Option<String>) {
+    let mut themes = Theme::built_in();
+    let mut error = None;
+    match storage::load(THEME_KEY).map(|text| Theme::from_text(&text, &themes)) {
+        Some(Ok(mut theme)) => {
+            // a theme without a name of its own has the name of the one it started from
+            if themes.iter().any(|built_in| built_in.name == theme.name) {
+                theme.name += " (theme.txt)";
+            }
+            themes.push(theme);
+        }
+        Some(Err(err)) => error = Some(format!("Could not read {}, {}", THEME_KEY, err)),
+        None => (),
+    }
+    (themes, error)
+}
+
+fn board_game_state(board:&Board) -> GameState {
+    match board.state() {
+        BoardState::Playing => GameState::GameRunning,
+        BoardState::Won => GameState::GameWon,
+        BoardState::Lost => GameState::GameLost,
+    }
+}
 
+// short seeds are easier to share than the full u64 range
+fn new_seed() -> u64 {
+    gen_range(0, 1_000_000)
 }
 
 #[macroquad::main(window_conf)]
 async fn main() {
     srand(macroquad::miniquad::date::now() as u64);
-    let assets: Assets = Default::default();
-    let mut game: Game = Game::new(8, 8, 12);
+    // built-in themes and the player's own, and the one picked on the settings screen
+    let mut settings = storage::load(SETTINGS_KEY).map(|text| Settings::from_text(&text)).unwrap_or_default();
+    let (themes, theme_error) = load_themes();
+    let mut theme_index = themes.iter().position(|theme| theme.name == settings.theme).unwrap_or(0);
+    let mut assets = Assets::new(&themes[theme_index], &settings).unwrap_or_else(|err| {
+        eprintln!("Could not load the {} theme: {}", themes[theme_index].name, err);
+        theme_index = 0;
+        Assets::new(&themes[0], &settings).expect("the default theme is built in")
+    });
+    let mut settings_message = theme_error;
+    let sounds = Sounds::load().await;
+    // M was pressed, "Sound off" / "Sound on" shows until then
+    let mut mute_message_until = 0.0;
+    let mut options = BoardOptions::default();
+    let mut game: Game = Game::new(8, 8, 12, new_seed(), options);
+    // unfinished game from the last session, offered by the "Continue" button
+    let mut saved_game = storage::load(SAVE_KEY).and_then(|text| SavedGame::from_text(&text).ok()).filter(|saved| !saved.board.is_over());
+
+    // the web build can't see the window closing, so it saves after every move instead
+    let save_every_move = cfg!(target_arch = "wasm32");
+    prevent_quit();
 
     let mut state = GameState::MeinMenu;
-    let mut grid: Vec<Tile> = vec![Tile { revealed: false, has_mine: false, flagged: false, adjacent_mines: 0 }; game.tiles as usize];
     let mut level_duration: f64 = 0.0;
     let mut level_start_time = 0.0;
+    let mut no_hint_until = 0.0;
+    let mut no_guess_failed_until = 0.0;
+    let mut show_heatmap = false;
+    // result of the last export, shown for a while
+    let mut export_message: Option<(String, f64)> = None;
+    let mut fullscreen = false;
+    let mut camera = BoardCamera::new();
 
     // main menu screen config
-    let mut selected_button: Option<usize> = None;
-    let title_padding = 20.0;
+    let mut selected_button: Option<MenuAction> = None;
+    // button picked with the arrow keys, Enter clicks it
+    let mut menu_focus: Option<usize> = None;
+    // seed typed into the menu, a random one is used while it is empty
+    let mut seed_input = String::new();
+    let mut editing_seed = false;
+    // why "Load board" didn't work
+    let mut load_error: Option<String> = None;
+
+    // level editor, with the result of the last save and the tile it can be started from
+    let mut editor = Editor::new(8, 8, options);
+    let mut editor_message: Option<String> = None;
+    let mut editor_start: Option<usize> = None;
+    let mut editor_cursor: Option<usize> = None;
+    let mut custom = CustomBoard::new(EXPERT);
+
+    // replay viewer
+    let mut has_replays = storage::load(LAST_REPLAY_KEY).is_some();
+    let mut replays: Vec<(String, Recording)> = Vec::new();
+    let mut replay_index = 0;
+    let mut viewer: Option<ReplayViewer> = None;
+
+    // finished games, a damaged store is left alone rather than overwritten
+    let mut stats = match storage::load(STATS_KEY).map(|text| Stats::from_text(&text)) {
+        None => Some(Stats::default()),
+        Some(Ok(stats)) => Some(stats),
+        Some(Err(err)) => {
+            eprintln!("Could not read the statistics: {}", err);
+            None
+        }
+    };
+    let mut stats_level = 0;
 
     loop {
-        clear_background(BACKGROUND);
-        let tile_size = calculate_tile_size(game.rows, game.columns, MAX_TILE_SIZE);
+        if is_quit_requested() {
+            // an unfinished game from the last session stays until a new one is started
+            if !matches!(state, GameState::MeinMenu | GameState::CustomMenu | GameState::Statistics | GameState::Replay | GameState::Editor | GameState::Settings | GameState::Sound) ||
+               saved_game.is_none() {
+                write_save(&game, &state, level_duration);
+            }
+            break;
+        }
 
-        let screen_width = screen_width();
-        let screen_height = screen_height();
+        clear_background(assets.colors.background);
+        // F11 switches between the window and fullscreen, either can be resized freely
+        if is_key_pressed(KeyCode::F11) {
+            fullscreen = !fullscreen;
+            set_fullscreen(fullscreen);
+        }
+        // M mutes and unmutes the sound on any screen
+        if is_key_pressed(KeyCode::M) {
+            settings.muted = !settings.muted;
+            settings_message = save_settings(&settings);
+            mute_message_until = get_time() + 2.0;
+        }
+        // laid out for the board on screen
+        let shown_grid = match (&state, &viewer) {
+            (GameState::Editor, _) => editor.board().grid(),
+            (GameState::Replay, Some(viewer)) => viewer.board.grid(),
+            _ => game.board.grid(),
+        };
+        camera.show(shown_grid);
+        let layout = ScreenLayout::new(shown_grid, &camera, settings.ui_scale);
+        let camera_drag = matches!(state, GameState::GameRunning | GameState::GameWon | GameState::GameLost | GameState::Editor | GameState::Replay) &&
+                          camera.update(&layout);
 
         match state {
             GameState::MeinMenu => {
-                let mut my_level = LEVEL_1;
-
-                // Calculate scaling factor for screen size
-                let scale_factor = (screen_width / WINDOW_WIDTH as f32).min(screen_height / WINDOW_HEIGHT as f32).clamp(0.5, 2.0);
-
-                // Draw the title
-                let title = "RUSTY MINES";
-                let title_size = 50.0 * scale_factor;
-                let title_dimensions = measure_text(title, Some(&assets.font), title_size as u16, 1.0);
-                let text1_x = screen_width / 2.0 - title_dimensions.width / 2.0;
-                let text1_y = title_dimensions.height + title_padding * scale_factor;
-                draw_text_ex(
-                    title,
-                    text1_x,
-                    text1_y,
-                    TextParams {
-                        font: Some(&assets.font),
-                        font_size: title_size as u16,
-                        color: ORANGE,
-                        ..Default::default()
+                let menu_top = draw_menu_title(&assets, "RUSTY MINES", &layout);
+
+                // Typing digits while the seed button is active edits the seed
+                while let Some(c) = get_char_pressed() {
+                    if editing_seed && c.is_ascii_digit() && seed_input.len() < 18 {
+                        seed_input.push(c);
                     }
-                );
+                }
+                if editing_seed && is_key_pressed(KeyCode::Backspace) {
+                    seed_input.pop();
+                }
+                let seed = seed_input.parse::<u64>().ok();
 
                 // Draw the buttons
-                let button_labels = ["Beginner", "Intermediate", "Expert"];
-
-                let button_height = 60.0 * scale_factor;
-                let button_width = 300.0 * scale_factor;
-                let button_spacing = 24.0 * scale_factor;
-                let total_height = button_labels.len() as f32 * (button_height + button_spacing) - button_spacing;
-
-                for (i, label) in button_labels.iter().enumerate() {
-                    let y = screen_height / 2.0 - total_height / 2.0 + i as f32 * (button_height + button_spacing);
-                    let x = screen_width / 2.0 - button_width / 2.0;
-
-                    // Determine button color
-                    let mouse_position = mouse_position();
-                    let is_hovered = mouse_position.0 >= x && mouse_position.0 <= x + button_width &&
-                                     mouse_position.1 >= y && mouse_position.1 <= y + button_height;
-                    let color = if Some(i) == selected_button {
-                        RED
-                    } else if is_hovered {
-                        DARKGRAY
-                    } else {
-                        GRAY
-                    };
-
-                    // Draw button background
-                    draw_rectangle(x, y, button_width, button_height, color);
-
-                    // Draw button label
-                    let label_size = 36.0 * scale_factor;
-                    let label_dimensions = measure_text(label, Some(&assets.font), label_size as u16, 1.0);
-                    draw_text_ex(
-                        label,
-                        x + button_width / 2.0 - label_dimensions.width / 2.0,
-                        y + button_height / 2.0 + label_dimensions.height / 4.0,
-                        TextParams {
-                            font: Some(&assets.font),
-                            font_size: label_size as u16,
-                            color: ORANGE,
-                            ..Default::default()
-                        }
-                    );
-
-                    // Check for mouse click
-                    if is_mouse_button_pressed(MouseButton::Left) && is_hovered {
-                        selected_button = Some(i);
-                        my_level = match i {
-                            0 => LEVEL_1,
-                            1 => LEVEL_2,
-                            2 => LEVEL_3,
-                            _ => LEVEL_1,
+                let no_guess = options.generation == Generation::NoGuess;
+                let hex = options.topology == Topology::Hex;
+                let flag_to_win = options.win_rule == WinRule::FlagMines;
+                let seed_label = match (seed, editing_seed) {
+                    (_, true) => format!("Seed: {}_", seed_input),
+                    (Some(seed), false) => format!("Seed: {}", seed),
+                    (None, false) => "Seed: random".to_owned(),
+                };
+                let mut buttons: Vec<(String, MenuAction)> = Vec::new();
+                if saved_game.is_some() {
+                    buttons.push(("Continue".to_owned(), MenuAction::Continue));
+                }
+                for (name, level) in LEVELS {
+                    buttons.push((name.to_owned(), MenuAction::Play(level)));
+                }
+                buttons.push(("Custom".to_owned(), MenuAction::Custom));
+                buttons.push(("Load board".to_owned(), MenuAction::LoadBoard));
+                buttons.push(("Editor".to_owned(), MenuAction::Editor));
+                buttons.push((if no_guess { "No guessing: ON" } else { "No guessing: OFF" }.to_owned(), MenuAction::ToggleNoGuess));
+                buttons.push((if hex { "Hex grid: ON" } else { "Hex grid: OFF" }.to_owned(), MenuAction::ToggleHex));
+                buttons.push((if options.wrap { "Wrap edges: ON" } else { "Wrap edges: OFF" }.to_owned(), MenuAction::ToggleWrap));
+                buttons.push((if options.multi_mine { "Multi-mine: ON" } else { "Multi-mine: OFF" }.to_owned(), MenuAction::ToggleMultiMine));
+                buttons.push((if flag_to_win { "Win by: flagging" } else { "Win by: revealing" }.to_owned(), MenuAction::ToggleWinRule));
+                buttons.push((seed_label, MenuAction::EditSeed));
+                if has_replays {
+                    buttons.push(("Replays".to_owned(), MenuAction::Replays));
+                }
+                buttons.push(("Statistics".to_owned(), MenuAction::Statistics));
+                buttons.push(("Settings".to_owned(), MenuAction::Settings));
+
+                let styled: Vec<(&str, Option<Color>)> = buttons
+                    .iter()
+                    .map(|(label, action)| {
+                        let highlight = if Some(*action) == selected_button {
+                            Some(assets.colors.alert)
+                        } else if (*action == MenuAction::ToggleNoGuess && no_guess) || (*action == MenuAction::ToggleHex && hex) ||
+                                  (*action == MenuAction::ToggleWrap && options.wrap) ||
+                                  (*action == MenuAction::ToggleMultiMine && options.multi_mine) ||
+                                  (*action == MenuAction::ToggleWinRule && flag_to_win) ||
+                                  (*action == MenuAction::EditSeed && editing_seed) {
+                            Some(assets.colors.button_on)
+                        } else {
+                            None
                         };
-                        game = Game::new(my_level.rows, my_level.columns, my_level.mines);
-                        grid = vec![Tile { revealed: false, has_mine: false, flagged: false, adjacent_mines: 0 }; game.tiles as usize];
-                        println!("Button clicked: {}", label);
-                        println!("Tiles: {}", game.tiles);
-                        println!("Rows: {}", game.rows);
-                        println!("Cols: {}", game.columns);
-                        initialize_grid(&mut grid, game.tiles);
-                        state = GameState::GameRunning;
-                        level_start_time = get_time();
+                        (label.as_str(), highlight)
+                    })
+                    .collect();
+
+                if let Some(clicked) = draw_menu_buttons(&assets, &styled, menu_top, &layout, &mut menu_focus) {
+                    let (_, action) = &buttons[clicked];
+                    match *action {
+                        MenuAction::Continue => {
+                            if let Some(saved) = saved_game.take() {
+                                level_start_time = get_time() - saved.elapsed;
+                                game = Game::from_board(saved.board);
+                                // only keep the recording if it belongs to this board
+                                game.recording = storage::load(SAVE_REPLAY_KEY)
+                                    .and_then(|text| Recording::from_text(&text).ok())
+                                    .filter(|recording| recording.seed == game.board.seed() && recording.board_after(recording.actions.len()).tiles() == game.board.tiles());
+                                state = GameState::GameRunning;
+                            }
+                        }
+                        MenuAction::Play(my_level) => {
+                            selected_button = Some(*action);
+                            game = Game::new(my_level.rows, my_level.columns, my_level.mines, seed.unwrap_or_else(new_seed), options);
+                            saved_game = None;
+                            editing_seed = false;
+                            state = GameState::GameRunning;
+                            level_start_time = get_time();
+                        }
+                        MenuAction::LoadBoard => match load_layout() {
+                            Ok(layout) => {
+                                game = Game::from_board(Board::from_layout(&layout, options));
+                                saved_game = None;
+                                editing_seed = false;
+                                load_error = None;
+                                state = GameState::GameRunning;
+                                level_start_time = get_time();
+                            }
+                            Err(message) => load_error = Some(message),
+                        },
+                        MenuAction::Editor => {
+                            // carry on with the saved board, or start an empty one the size of the last game
+                            editor = match load_layout() {
+                                Ok(layout) => Editor::from_layout(&layout, options),
+                                Err(_) => Editor::new(game.board.rows(), game.board.columns(), options),
+                            };
+                            editor_message = None;
+                            editor_start = None;
+                            editor_cursor = None;
+                            editing_seed = false;
+                            menu_focus = None;
+                            state = GameState::Editor;
+                        }
+                        MenuAction::Statistics => state = GameState::Statistics,
+                        MenuAction::Settings => {
+                            editing_seed = false;
+                            menu_focus = None;
+                            state = GameState::Settings;
+                        }
+                        MenuAction::Custom => {
+                            editing_seed = false;
+                            menu_focus = None;
+                            state = GameState::CustomMenu;
+                        }
+                        MenuAction::ToggleNoGuess => {
+                            options.generation = if no_guess { Generation::Random } else { Generation::NoGuess };
+                        }
+                        MenuAction::ToggleHex => {
+                            options.topology = if hex { Topology::Square } else { Topology::Hex };
+                        }
+                        MenuAction::ToggleWrap => options.wrap = !options.wrap,
+                        MenuAction::ToggleMultiMine => options.multi_mine = !options.multi_mine,
+                        MenuAction::ToggleWinRule => {
+                            options.win_rule = if flag_to_win { WinRule::RevealSafe } else { WinRule::FlagMines };
+                        }
+                        MenuAction::EditSeed => editing_seed = !editing_seed,
+                        MenuAction::Replays => {
+                            replays = load_replays();
+                            replay_index = 0;
+                            if let Some((name, recording)) = replays.first() {
+                                viewer = Some(ReplayViewer::new(name.clone(), recording.clone()));
+                                state = GameState::Replay;
+                            }
+                        }
                     }
                 }
 
-                if is_key_pressed(KeyCode::Enter) {
-                    initialize_grid(&mut grid, game.tiles);
+                if let Some(message) = &load_error {
+                    draw_status_message(&assets, message, &layout);
+                }
+
+                // without a focused button Enter replays the last level
+                if is_key_pressed(KeyCode::Enter) && state == GameState::MeinMenu && menu_focus.is_none() {
+                    game = Game::new(game.board.rows(), game.board.columns(), game.board.mines(), seed.unwrap_or_else(new_seed), options);
+                    saved_game = None;
+                    editing_seed = false;
                     state = GameState::GameRunning;
                     level_start_time = get_time();
                 }
             }
 
+            GameState::CustomMenu => {
+                let menu_top = draw_menu_title(&assets, "CUSTOM BOARD", &layout);
+
+                // the active field takes the typed digits
+                while let Some(c) = get_char_pressed() {
+                    if let Some(field) = custom.editing {
+                        if c.is_ascii_digit() && custom.inputs[field].len() < 6 {
+                            custom.inputs[field].push(c);
+                        }
+                    }
+                }
+                if is_key_pressed(KeyCode::Backspace) {
+                    if let Some(field) = custom.editing {
+                        custom.inputs[field].pop();
+                    }
+                }
+                if is_key_pressed(KeyCode::Tab) {
+                    custom.editing = Some(custom.editing.map_or(0, |field| (field + 1) % CUSTOM_FIELDS.len()));
+                }
+                let level = custom.level();
+
+                let mut buttons: Vec<(String, Option<Color>)> = CUSTOM_FIELDS
+                    .iter()
+                    .enumerate()
+                    .map(|(field, name)| {
+                        let editing = custom.editing == Some(field);
+                        let label = format!("{}: {}{}", name, custom.inputs[field], if editing { "_" } else { "" });
+                        (label, editing.then_some(assets.colors.button_on))
+                    })
+                    .collect();
+                buttons.push(("Play".to_owned(), level.is_err().then_some(assets.colors.button_disabled)));
+                buttons.push(("Back".to_owned(), None));
+                let styled: Vec<(&str, Option<Color>)> = buttons.iter().map(|(label, color)| (label.as_str(), *color)).collect();
+
+                let mut play = is_key_pressed(KeyCode::Enter) && menu_focus.is_none();
+                match draw_menu_buttons(&assets, &styled, menu_top, &layout, &mut menu_focus) {
+                    Some(field) if field < CUSTOM_FIELDS.len() => {
+                        custom.editing = if custom.editing == Some(field) { None } else { Some(field) };
+                    }
+                    Some(button) if button == CUSTOM_FIELDS.len() => play = true,
+                    Some(_) => state = GameState::MeinMenu,
+                    None => (),
+                }
+                if is_key_pressed(KeyCode::Escape) {
+                    state = GameState::MeinMenu;
+                }
+                if state == GameState::MeinMenu {
+                    menu_focus = None;
+                }
+
+                match level {
+                    Ok(level) if play => {
+                        let seed = seed_input.parse::<u64>().ok();
+                        game = Game::new(level.rows, level.columns, level.mines, seed.unwrap_or_else(new_seed), options);
+                        saved_game = None;
+                        custom.editing = None;
+                        state = GameState::GameRunning;
+                        level_start_time = get_time();
+                    }
+                    Ok(_) => (),
+                    Err(message) => draw_status_message(&assets, &message, &layout),
+                }
+            }
+
             GameState::GameRunning => {
                 level_duration = get_time() - level_start_time;
 
-                // Check game status, once mines are placed (after first click). If game is lost or won, update game state
-                if game.mines_placed_in_grid {
-                    update_game_state(&grid, &mut state, game.mines);
-                }
-
-                // Calculate grid offsets to center the grid on the screen
-                let (x_offset, y_offset) = calculate_grid_offsets(game.rows, game.columns, MAX_TILE_SIZE);
-
-                draw_grid(&grid, &assets, &state, game.rows, game.columns, game.cell_size, x_offset, y_offset);
-
-                if is_mouse_button_down(MouseButton::Left) && is_mouse_button_down(MouseButton::Right) {
-                    let (mouse_x, mouse_y) = mouse_position();
-                    let tile_id = screen_to_tile_id(mouse_x - x_offset, mouse_y - y_offset, game.columns as i32, game.rows as i32, tile_size);
-                    if tile_id >= 0 {
-                        reveal_all_adjacent_tiles(&mut grid, tile_id as usize, game.rows, game.columns);
-                    }
-                } else if is_mouse_button_pressed(MouseButton::Right) {
-                    let (mouse_x, mouse_y) = mouse_position();
-                    let tile_id = screen_to_tile_id(mouse_x - x_offset, mouse_y - y_offset, game.columns as i32, game.rows as i32, tile_size);
-                    if tile_id >= 0 {
-                        let flags_remaining = game.mines - game.mines_flagged;
-                        let tile_flag_changed: bool = flag_tile(&mut grid, tile_id as usize, flags_remaining);
-                        if tile_flag_changed {
-                            if grid[tile_id as usize].flagged {
-                                game.mines_flagged += 1;
-                            } else {
-                                game.mines_flagged -= 1;
-                            }
+                if show_heatmap && game.heatmap_stale && game.board.mines_placed() {
+                    game.heatmap = game.board.mine_probabilities();
+                    game.heatmap_stale = false;
+                }
+                let cursor = game.cursor;
+                game.cursor = move_cursor(&game.board, game.cursor);
+                if let Some(tile_id) = game.cursor.filter(|_| game.cursor != cursor) {
+                    camera.reveal_point(tile_position(game.board.grid(), tile_id), &layout);
+                }
+                let overlay = GridOverlay {
+                    hint: game.hint,
+                    heatmap: game.heatmap.as_deref().filter(|_| show_heatmap),
+                    cursor: game.cursor,
+                };
+                draw_grid(&game.board, &assets, &state, &layout, &overlay);
+
+                // a drag pans the view instead of playing
+                let tile_id = layout.tile_at(&game.board, mouse_position()).filter(|_| !camera_drag);
+
+                // chord when one button is pressed while the other is held
+                let left_pressed = is_mouse_button_pressed(MouseButton::Left);
+                let right_pressed = is_mouse_button_pressed(MouseButton::Right);
+                let chord_pressed = (left_pressed && is_mouse_button_down(MouseButton::Right)) ||
+                                    (right_pressed && is_mouse_button_down(MouseButton::Left));
+                let mut command = None;
+                if let Some(tile_id) = tile_id {
+                    if chord_pressed {
+                        command = Some((ActionKind::Chord, tile_id));
+                    } else if right_pressed {
+                        command = Some((ActionKind::Flag, tile_id));
+                    } else if left_pressed {
+                        command = Some((ActionKind::Reveal, tile_id));
+                    }
+                }
+                if left_pressed || right_pressed {
+                    // the mouse took over, hide the keyboard cursor
+                    game.cursor = None;
+                }
+
+                // Space or Enter reveals, F flags and C chords under the keyboard cursor
+                if let Some(tile_id) = game.cursor {
+                    if is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter) {
+                        command = Some((ActionKind::Reveal, tile_id));
+                    } else if is_key_pressed(KeyCode::F) {
+                        command = Some((ActionKind::Flag, tile_id));
+                    } else if is_key_pressed(KeyCode::C) {
+                        command = Some((ActionKind::Chord, tile_id));
+                    }
+                }
+
+                // Z undoes the last move and Y redoes it
+                if is_key_pressed(KeyCode::Z) {
+                    command = Some((ActionKind::Undo, 0));
+                } else if is_key_pressed(KeyCode::Y) {
+                    command = Some((ActionKind::Redo, 0));
+                }
+
+                let input_used = command.is_some();
+                let revealed_before = if input_used { revealed_count(&game.board) } else { 0 };
+                let mut flag_changed = false;
+                match command {
+                    Some((ActionKind::Chord, tile_id)) => {
+                        game.board.chord(tile_id);
+                        game.record(ActionKind::Chord, tile_id, level_duration);
+                    }
+                    Some((ActionKind::Flag | ActionKind::Unflag, tile_id)) => {
+                        flag_changed = game.board.flag(tile_id);
+                        if flag_changed {
+                            let kind = if game.board.tile(tile_id).flagged() { ActionKind::Flag } else { ActionKind::Unflag };
+                            game.record(kind, tile_id, level_duration);
                         }
                     }
-                } else if is_mouse_button_pressed(MouseButton::Left) {
-                    let (mouse_x, mouse_y) = mouse_position();
-                    let tile_id = screen_to_tile_id(mouse_x - x_offset, mouse_y - y_offset, game.columns as i32, game.rows as i32, tile_size);
-                    if tile_id >= 0 {
-                        if game.mines_placed_in_grid == false {
-                            print!("Placing mines in grid...");
-                            place_mines(&mut grid, game.mines, game.tiles, tile_id as usize);
-                            game.mines_placed_in_grid = true;
+                    Some((ActionKind::Reveal, tile_id)) => {
+                        let placing = !game.board.mines_placed();
+                        game.board.reveal(tile_id);
+                        // no-guess generation ran out of time and fell back to random mines
+                        let board_options = game.board.options();
+                        if placing && board_options.generation == Generation::NoGuess && !board_options.multi_mine && !game.board.guess_free() {
+                            no_guess_failed_until = get_time() + 4.0;
                         }
-                        grid[tile_id as usize].adjacent_mines = num_adjacent_mines(&grid, game.rows, game.columns, tile_id as usize);
-                        reveal_tile(&mut grid, tile_id as usize, game.rows, game.columns);
+                        game.record(ActionKind::Reveal, tile_id, level_duration);
+                    }
+                    Some((ActionKind::Undo, _)) => {
+                        game.undo(level_duration);
                     }
+                    Some((ActionKind::Redo, _)) => {
+                        game.redo(level_duration);
+                    }
+                    None => (),
+                }
+                if input_used {
+                    game.heatmap_stale = true;
                 }
 
-                draw_top_ui_panel(screen_width, &assets.font, game.mines_flagged, game.mines, level_duration, tile_size);
-            }
+                // the end of the game drowns out the sound of the move that ended it
+                let opened = if input_used { revealed_count(&game.board).saturating_sub(revealed_before) } else { 0 };
+                let effect = match (game.board.state(), command) {
+                    (_, None) => None,
+                    (BoardState::Lost, _) => Some(Effect::Explosion),
+                    (BoardState::Won, _) => Some(Effect::Fanfare),
+                    (BoardState::Playing, _) if opened > 1 => Some(Effect::Cascade),
+                    (BoardState::Playing, Some((ActionKind::Chord, _))) => Some(Effect::Chord),
+                    (BoardState::Playing, Some((ActionKind::Reveal, _))) if opened == 1 => Some(Effect::Reveal),
+                    (BoardState::Playing, Some((ActionKind::Flag | ActionKind::Unflag, _))) if flag_changed => Some(Effect::Flag),
+                    (BoardState::Playing, _) => None,
+                };
+                if let Some(effect) = effect {
+                    sounds.play(effect, settings.sound_volume());
+                }
 
-            GameState::GameLost => {
-                // Calculate grid offsets to center the grid on the screen
-                let (x_offset, y_offset) = calculate_grid_offsets(game.rows, game.columns, MAX_TILE_SIZE);
-                draw_grid(&grid, &assets, &state, game.rows, game.columns, MAX_TILE_SIZE, x_offset, y_offset);
-
-                let text_1 = "BOOM! You Lost...";
-                let font1_size = (tile_size / 1.5) as u16;
-                let text1_size = measure_text(&text_1, Some(&assets.font), font1_size, 1.0);
-                let text1_x = screen_width / 2.0 - text1_size.width / 2.0;
-                let text1_y = screen_height / 2.0 - text1_size.height / 2.0;
-
-                let text2 = "Press ENTER to play again...";
-                let font2_size = (tile_size / 2.0) as u16;
-                let text2_size = measure_text(&text2, Some(&assets.font), font2_size, 1.0);
-                let text2_x = screen_width / 2.0 - text2_size.width / 2.0;
-                let text2_y = screen_height / 2.0 - text2_size.height / 2.0 + text1_size.height / 2.0 + 20.0;
-
-                let margin = 5.0;
-                let rectx = text1_x.min(text2_x) - margin;
-                let recty = text1_y.min(text2_y) - text1_size.height - margin;
-                let rectw = text1_size.width.max(text2_size.width) + margin + margin;
-                let recth = text1_size.height + 20.0 + text2_size.height + margin;
-                let rect_col: Color = Color::new(0.0, 0.0, 0.0, 0.5);
-                draw_rectangle(rectx, recty, rectw, recth, rect_col);
-
-                draw_text_ex(
-                    &text_1,
-                    text1_x,
-                    text1_y,
-                    TextParams {
-                        font: Some(&assets.font),
-                        font_size: font1_size as u16,
-                        color: RED,
-                        ..Default::default()
+                // E writes the mines of the board to a file
+                if is_key_pressed(KeyCode::E) {
+                    export_message = Some((export_layout(&game.board), get_time() + 3.0));
+                }
+
+                // P toggles the mine probability heatmap
+                if is_key_pressed(KeyCode::P) {
+                    show_heatmap = !show_heatmap;
+                }
+                if show_heatmap && !game.heatmap_stale && game.heatmap.is_none() {
+                    let message = if game.board.options().multi_mine {
+                        "No heatmap on multi-mine boards"
+                    } else {
+                        "No heatmap: too many possibilities or a wrong flag"
+                    };
+                    draw_status_message(&assets, message, &layout);
+                }
+
+                // H asks the solver for a provably safe tile
+                if is_key_pressed(KeyCode::H) {
+                    game.hint = game.board.hint();
+                    if game.hint.is_none() {
+                        no_hint_until = get_time() + 2.0;
                     }
-                );
+                }
+                if game.hint.is_some_and(|tile_id| game.board.tile(tile_id).revealed || game.board.tile(tile_id).flagged()) {
+                    game.hint = None;
+                }
+                if get_time() < no_hint_until {
+                    draw_status_message(&assets, "No safe move can be deduced", &layout);
+                } else if get_time() < no_guess_failed_until {
+                    draw_status_message(&assets, "No guess-free layout found in time, this board may need guessing", &layout);
+                }
 
-                draw_text_ex(
-                    &text2,
-                    text2_x,
-                    text2_y,
-                    TextParams {
-                        font: Some(&assets.font),
-                        font_size: font2_size as u16,
-                        color: WHITE,
-                        ..Default::default()
+                // If game is lost or won, update game state
+                match game.board.state() {
+                    BoardState::Won => state = GameState::GameWon,
+                    BoardState::Lost => state = GameState::GameLost,
+                    BoardState::Playing => (),
+                }
+                if state != GameState::GameRunning {
+                    game.save_replay();
+                    has_replays = true;
+                    if let (Some(stats), false) = (&mut stats, game.test_play) {
+                        record_game(stats, &game.board, level_duration);
                     }
-                );
+                }
+                if state != GameState::GameRunning || (save_every_move && input_used) {
+                    write_save(&game, &state, level_duration);
+                }
 
-                draw_top_ui_panel(screen_width, &assets.font, game.mines_flagged, game.mines, level_duration, tile_size);
+                // Escape leaves a test play for the editor, and saves any other game
+                // for the main menu's Continue
+                if is_key_pressed(KeyCode::Escape) && state == GameState::GameRunning {
+                    if game.test_play {
+                        state = GameState::Editor;
+                    } else {
+                        write_save(&game, &state, level_duration);
+                        saved_game = game.board.mines_placed().then(|| SavedGame { board: game.board.clone(), elapsed: level_duration });
+                        state = GameState::MeinMenu;
+                    }
+                }
+
+                draw_top_ui_panel(&layout, &assets, &game.board, level_duration);
+            }
+
+            GameState::GameLost => {
+                draw_grid(&game.board, &assets, &state, &layout, &GridOverlay::default());
+
+                let mut lines = vec![
+                    format!("Hints used: {}", game.board.hints_used()),
+                    "Press ENTER to play again...".to_owned(),
+                    "Press R to watch the replay".to_owned(),
+                    "Press Z to undo the last move".to_owned(),
+                    "Press E to export the board".to_owned(),
+                ];
+                if game.board.practice() {
+                    lines.insert(0, "Practice game - not ranked".to_owned());
+                }
+                lines.push(if game.test_play { "Press ESC to go back to the editor" } else { "Press ESC for the main menu" }.to_owned());
+                draw_end_overlay(&assets, "BOOM! You Lost...", assets.colors.alert, &lines, &layout);
+
+                draw_top_ui_panel(&layout, &assets, &game.board, level_duration);
 
                 if is_key_pressed(KeyCode::Enter) {
-                    initialize_grid(&mut grid, game.tiles);
+                    if game.test_play {
+                        game = test_game(&editor);
+                    } else {
+                        game.reset(new_seed());
+                    }
                     state = GameState::GameRunning;
-                    game.mines_flagged = 0;
-                    game.mines_placed_in_grid = false;
                     level_start_time = get_time();
                 }
+                if is_key_pressed(KeyCode::Escape) {
+                    state = if game.test_play { GameState::Editor } else { GameState::MeinMenu };
+                }
+                if is_key_pressed(KeyCode::R) {
+                    if let Some(recording) = &game.recording {
+                        replays = load_replays();
+                        replay_index = 0;
+                        viewer = Some(ReplayViewer::new("Last game".to_owned(), recording.clone()));
+                        state = GameState::Replay;
+                    }
+                }
+                if is_key_pressed(KeyCode::E) {
+                    export_message = Some((export_layout(&game.board), get_time() + 3.0));
+                }
+                // the finished game stays in the statistics, what follows is practice
+                if is_key_pressed(KeyCode::Z) && game.undo(level_duration) {
+                    game.heatmap_stale = true;
+                    state = GameState::GameRunning;
+                    write_save(&game, &state, level_duration);
+                }
             }
             GameState::GameWon => {
-                for tile in &mut grid {
-                    tile.revealed = true;
-                }
-                // Calculate grid offsets to center the grid on the screen
-                let (x_offset, y_offset) = calculate_grid_offsets(game.rows, game.columns, MAX_TILE_SIZE);
-                draw_grid(&grid, &assets, &state, game.rows, game.columns, MAX_TILE_SIZE, x_offset, y_offset);
-
-                let text1 = "You Won!";
-                let font1_size = (tile_size / 1.5) as u16;
-                let text1_size = measure_text(&text1, Some(&assets.font), font1_size, 1.0);
-                let text1_x = screen_width / 2.0 - text1_size.width / 2.0;
-                let text1_y = screen_height / 2.0 - text1_size.height / 2.0;
-
-                let text2 = "Press ENTER to play again...";
-                let font2_size = (tile_size / 2.0) as u16;
-                let text2_size = measure_text(&text2, Some(&assets.font), font2_size, 1.0);
-                let text2_x = screen_width / 2.0 - text2_size.width / 2.0;
-                let text2_y = screen_height / 2.0 - text2_size.height / 2.0 + text1_size.height / 2.0 + 20.0;
-
-                let margin = 5.0;
-                let rectx = text1_x.min(text2_x) - margin;
-                let recty = text1_y.min(text2_y) - text1_size.height - margin;
-                let rectw = text1_size.width.max(text2_size.width) + margin + margin;
-                let recth = text1_size.height + 20.0 + text2_size.height + margin;
-                let rect_col: Color = Color::new(0.0, 0.0, 0.0, 0.5);
-                draw_rectangle(rectx, recty, rectw, recth, rect_col);
-
-                draw_text_ex(
-                    &text1,
-                    text1_x,
-                    text1_y,
-                    TextParams {
-                        font: Some(&assets.font),
-                        font_size: font1_size as u16,
-                        color: GREEN,
-                        ..Default::default()
-                    }
-                );
-
-                draw_text_ex(
-                    &text2,
-                    text2_x,
-                    text2_y,
-                    TextParams {
-                        font: Some(&assets.font),
-                        font_size: font2_size as u16,
-                        color: WHITE,
-                        ..Default::default()
-                    }
-                );
+                draw_grid(&game.board, &assets, &state, &layout, &GridOverlay::default());
+
+                let mut lines = vec![
+                    format!(
+                        "3BV: {} | 3BV/s: {:.2} | IOE: {:.2}",
+                        game.board.three_bv().unwrap_or(0),
+                        game.board.three_bv_per_second(level_duration),
+                        game.board.efficiency().unwrap_or(0.0)
+                    ),
+                    format!("Hints used: {}", game.board.hints_used()),
+                    "Press ENTER to play again...".to_owned(),
+                    "Press R to watch the replay".to_owned(),
+                    "Press Z to undo the last move".to_owned(),
+                    "Press E to export the board".to_owned(),
+                ];
+                if game.board.practice() {
+                    lines.insert(0, "Practice game - not ranked".to_owned());
+                }
+                lines.push(if game.test_play { "Press ESC to go back to the editor" } else { "Press ESC for the main menu" }.to_owned());
+                draw_end_overlay(&assets, "You Won!", assets.colors.success, &lines, &layout);
 
-                draw_top_ui_panel(screen_width, &assets.font, game.mines_flagged, game.mines, level_duration, tile_size);
+                draw_top_ui_panel(&layout, &assets, &game.board, level_duration);
 
                 if is_key_pressed(KeyCode::Enter) {
-                    initialize_grid(&mut grid, game.tiles);
+                    if game.test_play {
+                        game = test_game(&editor);
+                    } else {
+                        game.reset(new_seed());
+                    }
                     state = GameState::GameRunning;
                     level_start_time = get_time();
-                    game.mines_flagged = 0;
-                    game.mines_placed_in_grid = false;
                 }
+                if is_key_pressed(KeyCode::Escape) {
+                    state = if game.test_play { GameState::Editor } else { GameState::MeinMenu };
+                }
+                if is_key_pressed(KeyCode::R) {
+                    if let Some(recording) = &game.recording {
+                        replays = load_replays();
+                        replay_index = 0;
+                        viewer = Some(ReplayViewer::new("Last game".to_owned(), recording.clone()));
+                        state = GameState::Replay;
+                    }
+                }
+                if is_key_pressed(KeyCode::E) {
+                    export_message = Some((export_layout(&game.board), get_time() + 3.0));
+                }
+                // the finished game stays in the statistics, what follows is practice
+                if is_key_pressed(KeyCode::Z) && game.undo(level_duration) {
+                    game.heatmap_stale = true;
+                    state = GameState::GameRunning;
+                    write_save(&game, &state, level_duration);
+                }
+            }
+
+            GameState::Editor => {
+                let cursor = editor_cursor;
+                editor_cursor = move_cursor(editor.board(), editor_cursor);
+                if let Some(tile_id) = editor_cursor.filter(|_| editor_cursor != cursor) {
+                    camera.reveal_point(tile_position(editor.board().grid(), tile_id), &layout);
+                }
+                let overlay = GridOverlay { hint: editor_start, cursor: editor_cursor, ..Default::default() };
+                draw_grid(editor.board(), &assets, &state, &layout, &overlay);
+
+                // clicking a tile, or Space / Enter under the keyboard cursor, adds or removes its mine
+                let mut toggled = None;
+                if is_mouse_button_pressed(MouseButton::Left) && !camera_drag {
+                    toggled = layout.tile_at(editor.board(), mouse_position());
+                    if toggled.is_some() {
+                        editor_cursor = None;
+                    }
+                }
+                if let Some(tile_id) = editor_cursor {
+                    if is_key_pressed(KeyCode::Space) || is_key_pressed(KeyCode::Enter) {
+                        toggled = Some(tile_id);
+                    }
+                }
+                if toggled.is_some_and(|tile_id| editor.toggle_mine(tile_id)) {
+                    editor_message = None;
+                    editor_start = None;
+                }
+
+                // T tests the board, E saves it, Delete clears it and Escape goes back
+                let mut action = draw_panel_buttons(&assets, &["Test play", "Save", "Clear", "Back"], &layout);
+                if is_key_pressed(KeyCode::T) {
+                    action = Some(0);
+                } else if is_key_pressed(KeyCode::E) {
+                    action = Some(1);
+                } else if is_key_pressed(KeyCode::Delete) {
+                    action = Some(2);
+                } else if is_key_pressed(KeyCode::Escape) {
+                    action = Some(3);
+                }
+                match action {
+                    Some(0) => {
+                        game = test_game(&editor);
+                        saved_game = None;
+                        state = GameState::GameRunning;
+                        level_start_time = get_time();
+                    }
+                    Some(1) => {
+                        let (message, start) = save_edited_board(&editor);
+                        editor_message = Some(message);
+                        editor_start = start;
+                    }
+                    Some(2) => {
+                        editor.clear();
+                        editor_message = None;
+                        editor_start = None;
+                    }
+                    Some(_) => state = GameState::MeinMenu,
+                    None => (),
+                }
+
+                let status = editor_message.clone().unwrap_or_else(|| {
+                    format!("MINES: {} | 3BV: {} | Click a tile to add or remove a mine", editor.board().mines(), editor.board().three_bv().unwrap_or(0))
+                });
+                draw_status_message(&assets, &status, &layout);
+            }
+
+            GameState::Statistics => {
+                let top = draw_menu_title(&assets, "STATISTICS", &layout);
+
+                // Left/Right pick the level of the leaderboard
+                if is_key_pressed(KeyCode::Right) {
+                    stats_level = (stats_level + 1) % LEVELS.len();
+                }
+                if is_key_pressed(KeyCode::Left) {
+                    stats_level = (stats_level + LEVELS.len() - 1) % LEVELS.len();
+                }
+                draw_stats_screen(&assets, stats.as_ref(), LEVELS[stats_level].0, top, &layout);
+                draw_status_message(&assets, "LEFT/RIGHT: level | ESC: back", &layout);
+                if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Enter) {
+                    state = GameState::MeinMenu;
+                }
+            }
+
+            GameState::Settings => {
+                let menu_top = draw_menu_title(&assets, "SETTINGS", &layout);
+                let on_off = |on:bool| if on { "ON" } else { "OFF" };
+                let highlight = |on:bool| on.then_some(assets.colors.button_on);
+                let buttons = [
+                    (format!("Theme: {}", themes[theme_index].name), None),
+                    (format!("Colour vision: {}", settings.color_vision.name()), highlight(settings.color_vision != ColorVision::Normal)),
+                    (format!("Bold outlines: {}", on_off(settings.bold_outlines)), highlight(settings.bold_outlines)),
+                    (format!("Patterned tiles: {}", on_off(settings.patterned_tiles)), highlight(settings.patterned_tiles)),
+                    (format!("Numbers: {}", if settings.text_numbers { "text" } else { "sprites" }), highlight(settings.text_numbers)),
+                    (format!("UI scale: {}%", (settings.ui_scale * 100.0).round()), highlight(settings.ui_scale != 1.0)),
+                    ("Sound".to_owned(), None),
+                    ("Back".to_owned(), None),
+                ];
+                let styled:Vec<(&str, Option<Color>)> = buttons.iter().map(|(label, color)| (label.as_str(), *color)).collect();
+
+                // clicking a setting steps it forward, Left/Right step the focused one
+                let mut change = None;
+                match draw_menu_buttons(&assets, &styled, menu_top, &layout, &mut menu_focus) {
+                    Some(6) => state = GameState::Sound,
+                    Some(7) => state = GameState::MeinMenu,
+                    Some(setting) => change = Some((setting, true)),
+                    None => (),
+                }
+                if let Some(setting) = menu_focus.filter(|setting| *setting < 6) {
+                    if is_key_pressed(KeyCode::Right) {
+                        change = Some((setting, true));
+                    } else if is_key_pressed(KeyCode::Left) {
+                        change = Some((setting, false));
+                    }
+                }
+                if let Some((setting, forward)) = change {
+                    let step = |index:usize, len:usize| if forward { (index + 1) % len } else { (index + len - 1) % len };
+                    match setting {
+                        0 => {
+                            theme_index = step(theme_index, themes.len());
+                            settings.theme = themes[theme_index].name.clone();
+                        }
+                        1 => {
+                            let index = ColorVision::ALL.iter().position(|vision| *vision == settings.color_vision).unwrap_or(0);
+                            settings.color_vision = ColorVision::ALL[step(index, ColorVision::ALL.len())];
+                        }
+                        2 => settings.bold_outlines = !settings.bold_outlines,
+                        3 => settings.patterned_tiles = !settings.patterned_tiles,
+                        4 => settings.text_numbers = !settings.text_numbers,
+                        _ => {
+                            let index = UI_SCALES.iter().position(|scale| *scale == settings.ui_scale).unwrap_or(1);
+                            settings.ui_scale = UI_SCALES[step(index, UI_SCALES.len())];
+                        }
+                    }
+                    // the new look shows from the next frame, without a restart
+                    match Assets::new(&themes[theme_index], &settings) {
+                        Ok(loaded) => {
+                            assets = loaded;
+                            settings_message = save_settings(&settings);
+                        }
+                        Err(err) => settings_message = Some(err),
+                    }
+                }
+
+                if let Some(message) = &settings_message {
+                    draw_status_message(&assets, message, &layout);
+                }
+                if is_key_pressed(KeyCode::Escape) {
+                    state = GameState::MeinMenu;
+                }
+                if state != GameState::Settings {
+                    menu_focus = None;
+                }
+            }
+
+            GameState::Sound => {
+                let menu_top = draw_menu_title(&assets, "SOUND", &layout);
+                let percent = |volume:f32| (volume * 100.0).round();
+                let buttons = [
+                    (format!("Master volume: {}%", percent(settings.master_volume)), None),
+                    (format!("Effects volume: {}%", percent(settings.effects_volume)), None),
+                    (format!("Mute (M): {}", if settings.muted { "ON" } else { "OFF" }), settings.muted.then_some(assets.colors.button_on)),
+                    ("Back".to_owned(), None),
+                ];
+                let styled:Vec<(&str, Option<Color>)> = buttons.iter().map(|(label, color)| (label.as_str(), *color)).collect();
+
+                let mut changed = false;
+                match draw_menu_buttons(&assets, &styled, menu_top, &layout, &mut menu_focus) {
+                    Some(2) => {
+                        settings.muted = !settings.muted;
+                        changed = true;
+                    }
+                    Some(3) => state = GameState::Settings,
+                    _ => (),
+                }
+
+                // the volumes are sliders: drag along them, or Left/Right in steps of 10%
+                let rects = menu_button_rects(buttons.len(), menu_top, &layout);
+                let (mouse_x, mouse_y) = mouse_position();
+                for (i, volume) in [&mut settings.master_volume, &mut settings.effects_volume].into_iter().enumerate() {
+                    let mut new_volume = *volume;
+                    if is_mouse_button_down(MouseButton::Left) && rects[i].contains(vec2(mouse_x, mouse_y)) {
+                        new_volume = (mouse_x - rects[i].x) / rects[i].w;
+                    }
+                    if menu_focus == Some(i) {
+                        if is_key_pressed(KeyCode::Right) {
+                            new_volume += 0.1;
+                        } else if is_key_pressed(KeyCode::Left) {
+                            new_volume -= 0.1;
+                        }
+                    }
+                    // in steps of 5%, so the saved volumes stay round
+                    new_volume = ((new_volume * 20.0).round() / 20.0).clamp(0.0, 1.0);
+                    if new_volume != *volume {
+                        *volume = new_volume;
+                        changed = true;
+                    }
+                    draw_slider(&assets, rects[i], *volume, &layout);
+                }
+                if changed {
+                    settings_message = save_settings(&settings);
+                    // a sample of the new volume
+                    sounds.play(Effect::Reveal, settings.sound_volume());
+                }
+
+                if let Some(message) = &settings_message {
+                    draw_status_message(&assets, message, &layout);
+                }
+                if is_key_pressed(KeyCode::Escape) {
+                    state = GameState::Settings;
+                }
+                if state != GameState::Sound {
+                    menu_focus = None;
+                }
+            }
+
+            GameState::Replay => {
+                let Some(viewer) = &mut viewer else {
+                    state = GameState::MeinMenu;
+                    next_frame().await;
+                    continue;
+                };
+                viewer.update(get_frame_time() as f64);
+
+                // Space pauses, Left/Right step through the moves, Up/Down change the speed
+                if is_key_pressed(KeyCode::Space) {
+                    if viewer.applied == viewer.recording.actions.len() {
+                        viewer.seek(0);
+                        viewer.time = 0.0;
+                    }
+                    viewer.playing = !viewer.playing;
+                }
+                if is_key_pressed(KeyCode::Right) {
+                    viewer.step(true);
+                }
+                if is_key_pressed(KeyCode::Left) {
+                    viewer.step(false);
+                }
+                if is_key_pressed(KeyCode::Up) {
+                    viewer.speed = (viewer.speed * 2.0).min(16.0);
+                }
+                if is_key_pressed(KeyCode::Down) {
+                    viewer.speed = (viewer.speed / 2.0).max(0.25);
+                }
+
+                let board_state = board_game_state(&viewer.board);
+                let overlay = GridOverlay { hint: viewer.last_tile(), ..GridOverlay::default() };
+                draw_grid(&viewer.board, &assets, &board_state, &layout, &overlay);
+                draw_top_ui_panel(&layout, &assets, &viewer.board, viewer.time);
+
+                let status = format!(
+                    "{} | move {}/{} | x{} | SPACE LEFT RIGHT UP DOWN TAB ESC",
+                    viewer.name,
+                    viewer.applied,
+                    viewer.recording.actions.len(),
+                    viewer.speed
+                );
+                draw_status_message(&assets, &status, &layout);
+
+                // Tab moves on to the next stored replay
+                if is_key_pressed(KeyCode::Tab) && !replays.is_empty() {
+                    replay_index = (replay_index + 1) % replays.len();
+                    let (name, recording) = &replays[replay_index];
+                    *viewer = ReplayViewer::new(name.clone(), recording.clone());
+                }
+                if is_key_pressed(KeyCode::Escape) {
+                    state = GameState::MeinMenu;
+                }
+            }
+        }
+
+        if get_time() < mute_message_until {
+            draw_status_message(&assets, if settings.muted { "Sound off" } else { "Sound on" }, &layout);
+        }
+        if let Some((message, until)) = &export_message {
+            if get_time() < *until && matches!(state, GameState::GameRunning | GameState::GameWon | GameState::GameLost) {
+                draw_status_message(&assets, message, &layout);
             }
         }
         next_frame().await;
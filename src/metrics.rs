@@ -0,0 +1,132 @@
+//! Measures of how hard a board is, as used by competitive players.
+//!
+//! The 3BV ("Bechtel's Board Benchmark Value") of a board is the smallest
+//! number of left clicks that clears it without flags: one click per opening
+//! (a connected area of zeros, which reveals its border for free) and one per
+//! number that does not border an opening.
+
+use crate::topology::Grid;
+use crate::Tile;
+
+/// Clicks made during a game, by button.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Clicks {
+    pub left: u32,
+    pub right: u32,
+    pub chord: u32,
+}
+
+impl Clicks {
+    pub fn total(&self) -> u32 {
+        self.left + self.right + self.chord
+    }
+}
+
+/// 3BV of a grid whose mines have been placed and counted with
+/// [`crate::rules::count_adjacent_mines`].
+pub fn three_bv(arr: &[Tile], grid: Grid) -> u32 {
+    count_three_bv(arr, grid).0
+}
+
+/// The part of the 3BV already cleared: openings that have been opened and
+/// revealed numbers that don't border one.
+pub fn solved_three_bv(arr: &[Tile], grid: Grid) -> u32 {
+    count_three_bv(arr, grid).1
+}
+
+// (total, solved)
+fn count_three_bv(arr: &[Tile], grid: Grid) -> (u32, u32) {
+    let is_opening = |tile_id: usize| !arr[tile_id].has_mine() && arr[tile_id].adjacent_mines == 0;
+
+    let mut cleared = vec![false; arr.len()];
+    let mut total = 0;
+    let mut solved = 0;
+
+    // one click per opening, clearing it and its border
+    for start in 0..arr.len() {
+        if cleared[start] || !is_opening(start) {
+            continue;
+        }
+        total += 1;
+        // the flood fill opens all of it at once, so any revealed zero means it was clicked
+        let mut opened = arr[start].revealed;
+        cleared[start] = true;
+        let mut queue = vec![start];
+        while let Some(tile_id) = queue.pop() {
+            for next_id in grid.neighbours(tile_id) {
+                if !cleared[next_id] {
+                    cleared[next_id] = true;
+                    if is_opening(next_id) {
+                        opened |= arr[next_id].revealed;
+                        queue.push(next_id);
+                    }
+                }
+            }
+        }
+        solved += opened as u32;
+    }
+
+    // one click per number left over
+    for tile_id in (0..arr.len()).filter(|&tile_id| !cleared[tile_id] && !arr[tile_id].has_mine()) {
+        total += 1;
+        solved += arr[tile_id].revealed as u32;
+    }
+    (total, solved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Layout;
+    use crate::rules::{count_adjacent_mines, reveal_tile};
+    use crate::{Board, BoardOptions, Topology};
+
+    fn board(text: &str) -> (Vec<Tile>, Grid) {
+        let layout = Layout::from_text(text).unwrap();
+        let mut tiles: Vec<Tile> = layout.mines.iter().map(|&mine| Tile { mines: mine as u8, ..Tile::default() }).collect();
+        let grid = Grid::new(layout.rows, layout.columns, Topology::Square, false);
+        count_adjacent_mines(&mut tiles, grid);
+        (tiles, grid)
+    }
+
+    #[test]
+    fn one_opening_that_borders_every_number_is_one_click() {
+        let (arr, grid) = board("*..\n...\n...\n");
+        assert_eq!(three_bv(&arr, grid), 1);
+    }
+
+    #[test]
+    fn numbers_away_from_openings_take_a_click_each() {
+        let (arr, grid) = board("*..*\n");
+        assert_eq!(three_bv(&arr, grid), 2);
+        let (arr, grid) = board("*.*\n...\n*.*\n");
+        assert_eq!(three_bv(&arr, grid), 5);
+    }
+
+    #[test]
+    fn solved_three_bv_counts_openings_and_numbers_revealed() {
+        // two one-tile openings in the corners, with every number on their borders
+        let (mut arr, grid) = board("*..\n...\n..*\n");
+        assert_eq!((three_bv(&arr, grid), solved_three_bv(&arr, grid)), (2, 0));
+        reveal_tile(&mut arr, 2, grid);
+        assert_eq!(solved_three_bv(&arr, grid), 1);
+        // a number of the other opening, revealed on its own, doesn't solve it
+        reveal_tile(&mut arr, 7, grid);
+        assert_eq!(solved_three_bv(&arr, grid), 1);
+        reveal_tile(&mut arr, 6, grid);
+        assert_eq!(solved_three_bv(&arr, grid), 2);
+    }
+
+    #[test]
+    fn efficiency_is_solved_three_bv_per_click() {
+        let layout = Layout::from_text("*.*\n...\n*.*\n").unwrap();
+        let mut board = Board::from_layout(&layout, BoardOptions::default());
+        assert_eq!(board.efficiency(), None);
+        board.reveal(1);
+        board.flag(0);
+        assert_eq!(board.efficiency(), Some(0.5));
+        board.reveal(4);
+        board.reveal(3);
+        assert_eq!(board.efficiency(), Some(0.75));
+    }
+}
@@ -0,0 +1,403 @@
+//! Exact mine probabilities for the unrevealed tiles.
+//!
+//! Tiles the solver can already prove are fixed first. The remaining unknown
+//! tiles next to a revealed number (the frontier) are split into groups that
+//! share no constraint, and every consistent mine layout of each group is
+//! enumerated. Unknown tiles away from the frontier share the mines that are
+//! left over, so each frontier layout is weighted by the number of ways those
+//! mines can be spread over them.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::solver::deduce;
+use crate::topology::Grid;
+use crate::Tile;
+
+/// Upper bound on backtracking steps per call, so huge open frontiers give
+/// up instead of stalling the caller.
+pub const MAX_SEARCH_STEPS: u64 = 2_000_000;
+
+struct Constraint {
+    tiles: Vec<usize>,
+    mines: i32,
+}
+
+// consistent layouts of one frontier group, bucketed by how many mines they use
+struct GroupLayouts {
+    tiles: Vec<usize>,
+    // layouts[k] = number of layouts with k mines
+    layouts: Vec<f64>,
+    // mine_counts[k][i] = number of those layouts with a mine on tiles[i]
+    mine_counts: Vec<Vec<f64>>,
+}
+
+/// Probability of a mine on every tile of the grid, from the visible numbers
+/// and flags only. Revealed tiles are 0.0 and flagged tiles 1.0.
+///
+/// Returns None if the visible state is contradictory (for example because
+/// of a wrong flag) or the frontier is too large to enumerate within
+/// [`MAX_SEARCH_STEPS`].
+pub fn mine_probabilities(arr: &[Tile], grid: Grid, total_mines: u32) -> Option<Vec<f64>> {
+    let deductions = deduce(arr, grid, total_mines);
+
+    // None = still unknown
+    let mut probabilities: Vec<Option<f64>> = arr
+        .iter()
+        .map(|tile| {
+            if tile.flagged() {
+                Some(1.0)
+            } else if tile.revealed {
+                Some(0.0)
+            } else {
+                None
+            }
+        })
+        .collect();
+    for &tile_id in &deductions.mines {
+        probabilities[tile_id] = Some(1.0);
+    }
+    for &tile_id in &deductions.safe {
+        probabilities[tile_id] = Some(0.0);
+    }
+
+    let known_mines = probabilities.iter().filter(|p| **p == Some(1.0)).count() as i32;
+    let mines_left = total_mines as i32 - known_mines;
+
+    // constraints from revealed numbers over the unknown tiles
+    let mut constraints = Vec::new();
+    for (tile_id, tile) in arr.iter().enumerate() {
+        if !tile.revealed || tile.has_mine() {
+            continue;
+        }
+        let mut tiles = Vec::new();
+        let mut mines = tile.adjacent_mines as i32;
+        for next_id in grid.neighbours(tile_id) {
+            match probabilities[next_id] {
+                None => tiles.push(next_id),
+                Some(1.0) => mines -= 1,
+                Some(_) => (),
+            }
+        }
+        if mines < 0 || mines > tiles.len() as i32 {
+            return None;
+        }
+        if !tiles.is_empty() {
+            constraints.push(Constraint { tiles, mines });
+        }
+    }
+
+    let groups = frontier_groups(&constraints);
+    let mut on_frontier = vec![false; arr.len()];
+    for &tile_id in groups.iter().flat_map(|(tiles, _)| tiles) {
+        on_frontier[tile_id] = true;
+    }
+    let interior: Vec<usize> =
+        (0..arr.len()).filter(|&tile_id| probabilities[tile_id].is_none() && !on_frontier[tile_id]).collect();
+
+    let mut steps = 0;
+    let mut layouts = Vec::with_capacity(groups.len());
+    for (tiles, group_constraints) in &groups {
+        let group_constraints: Vec<&Constraint> = group_constraints.iter().map(|&i| &constraints[i]).collect();
+        layouts.push(enumerate_group(tiles, &group_constraints, &mut steps)?);
+    }
+
+    // ways[m] = weight of the frontier using m mines in total, per group
+    // prefix[i] combines groups before i, suffix[i] groups from i on
+    let mut prefix = vec![vec![1.0]];
+    for group in &layouts {
+        prefix.push(convolve(prefix.last().unwrap(), &group.layouts));
+    }
+    let mut suffix = vec![vec![1.0]];
+    for group in layouts.iter().rev() {
+        suffix.push(convolve(suffix.last().unwrap(), &group.layouts));
+    }
+    suffix.reverse();
+    let all_groups = prefix.last().unwrap();
+
+    // weight of leaving `mines_left - m` mines for the interior tiles, relative to the best m
+    let interior_len = interior.len() as i32;
+    let log_weights: Vec<Option<f64>> = (0..all_groups.len() as i32)
+        .map(|m| {
+            let rest = mines_left - m;
+            (rest >= 0 && rest <= interior_len && all_groups[m as usize] > 0.0).then(|| ln_choose(interior_len, rest))
+        })
+        .collect();
+    let max_log = log_weights.iter().flatten().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max_log == f64::NEG_INFINITY {
+        return None;
+    }
+    let interior_weight: Vec<f64> = log_weights.iter().map(|w| w.map_or(0.0, |w| (w - max_log).exp())).collect();
+
+    let total: f64 = all_groups.iter().zip(&interior_weight).map(|(ways, weight)| ways * weight).sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    for (i, group) in layouts.iter().enumerate() {
+        let others = convolve(&prefix[i], &suffix[i + 1]);
+        for (t, &tile_id) in group.tiles.iter().enumerate() {
+            let mut weight = 0.0;
+            for (k, counts) in group.mine_counts.iter().enumerate() {
+                for (m, ways) in others.iter().enumerate() {
+                    weight += counts[t] * ways * interior_weight.get(k + m).copied().unwrap_or(0.0);
+                }
+            }
+            probabilities[tile_id] = Some(weight / total);
+        }
+    }
+
+    if !interior.is_empty() {
+        let expected: f64 = all_groups
+            .iter()
+            .enumerate()
+            .map(|(m, ways)| ways * interior_weight[m] * (mines_left - m as i32).max(0) as f64)
+            .sum();
+        let probability = expected / total / interior_len as f64;
+        for tile_id in interior {
+            probabilities[tile_id] = Some(probability);
+        }
+    }
+
+    Some(probabilities.into_iter().map(|p| p.unwrap_or(0.0)).collect())
+}
+
+// splits the constraints into groups that share tiles, returning each group's
+// tiles and the indices of its constraints
+fn frontier_groups(constraints: &[Constraint]) -> Vec<(Vec<usize>, Vec<usize>)> {
+    let mut by_tile: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, constraint) in constraints.iter().enumerate() {
+        for &tile_id in &constraint.tiles {
+            by_tile.entry(tile_id).or_default().push(i);
+        }
+    }
+
+    let mut seen = vec![false; constraints.len()];
+    let mut groups = Vec::new();
+    for start in 0..constraints.len() {
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut group_constraints = vec![start];
+        let mut tiles = Vec::new();
+        let mut in_group = HashSet::new();
+        let mut next = 0;
+        // breadth first, so neighbouring tiles end up next to each other in `tiles`
+        while next < group_constraints.len() {
+            for &tile_id in &constraints[group_constraints[next]].tiles {
+                if !in_group.insert(tile_id) {
+                    continue;
+                }
+                tiles.push(tile_id);
+                for &j in &by_tile[&tile_id] {
+                    if !seen[j] {
+                        seen[j] = true;
+                        group_constraints.push(j);
+                    }
+                }
+            }
+            next += 1;
+        }
+        groups.push((tiles, group_constraints));
+    }
+    groups
+}
+
+fn enumerate_group(tiles: &[usize], constraints: &[&Constraint], steps: &mut u64) -> Option<GroupLayouts> {
+    let index: HashMap<usize, usize> = tiles.iter().enumerate().map(|(i, &tile_id)| (tile_id, i)).collect();
+    let mut tile_constraints = vec![Vec::new(); tiles.len()];
+    for (c, constraint) in constraints.iter().enumerate() {
+        for tile_id in &constraint.tiles {
+            tile_constraints[index[tile_id]].push(c);
+        }
+    }
+
+    let mut search = Search {
+        tile_constraints,
+        targets: constraints.iter().map(|c| c.mines).collect(),
+        placed: vec![0; constraints.len()],
+        open: constraints.iter().map(|c| c.tiles.len() as i32).collect(),
+        assignment: vec![false; tiles.len()],
+        layouts: vec![0.0; tiles.len() + 1],
+        mine_counts: vec![vec![0.0; tiles.len()]; tiles.len() + 1],
+        steps,
+    };
+    if !search.run(0, 0) {
+        return None;
+    }
+
+    // keep the numbers small, only their ratios matter
+    let scale = search.layouts.iter().copied().fold(0.0, f64::max);
+    if scale == 0.0 {
+        return None;
+    }
+    Some(GroupLayouts {
+        tiles: tiles.to_vec(),
+        layouts: search.layouts.iter().map(|n| n / scale).collect(),
+        mine_counts: search.mine_counts.iter().map(|counts| counts.iter().map(|n| n / scale).collect()).collect(),
+    })
+}
+
+struct Search<'a> {
+    tile_constraints: Vec<Vec<usize>>,
+    targets: Vec<i32>,
+    placed: Vec<i32>,
+    open: Vec<i32>,
+    assignment: Vec<bool>,
+    layouts: Vec<f64>,
+    mine_counts: Vec<Vec<f64>>,
+    steps: &'a mut u64,
+}
+
+impl Search<'_> {
+    // returns false once the step budget is used up
+    fn run(&mut self, tile: usize, mines: usize) -> bool {
+        *self.steps += 1;
+        if *self.steps > MAX_SEARCH_STEPS {
+            return false;
+        }
+
+        if tile == self.assignment.len() {
+            self.layouts[mines] += 1.0;
+            for (i, &mine) in self.assignment.iter().enumerate() {
+                if mine {
+                    self.mine_counts[mines][i] += 1.0;
+                }
+            }
+            return true;
+        }
+
+        for mine in [false, true] {
+            let fits = self.tile_constraints[tile].iter().all(|&c| {
+                let placed = self.placed[c] + mine as i32;
+                // enough open tiles must remain to reach the target
+                placed <= self.targets[c] && placed + self.open[c] > self.targets[c]
+            });
+            if !fits {
+                continue;
+            }
+
+            for &c in &self.tile_constraints[tile] {
+                self.placed[c] += mine as i32;
+                self.open[c] -= 1;
+            }
+            self.assignment[tile] = mine;
+            let finished = self.run(tile + 1, mines + mine as usize);
+            for &c in &self.tile_constraints[tile] {
+                self.placed[c] -= mine as i32;
+                self.open[c] += 1;
+            }
+            self.assignment[tile] = false;
+            if !finished {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    result
+}
+
+fn ln_choose(n: i32, k: i32) -> f64 {
+    let k = k.min(n - k);
+    (1..=k).map(|i| ((n - k + i) as f64 / i as f64).ln()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{count_adjacent_mines, place_mines, reveal_tile};
+    use crate::Topology;
+
+    // the share of the layouts consistent with the visible tiles that put a
+    // mine on each tile, by trying every way to spread the unflagged mines
+    fn brute_force(arr: &[Tile], grid: Grid, total_mines: u32) -> Vec<f64> {
+        let unknown: Vec<usize> = (0..arr.len()).filter(|&tile_id| !arr[tile_id].revealed && !arr[tile_id].flagged()).collect();
+        let flags = arr.iter().filter(|tile| tile.flagged()).count() as u32;
+        let mut mine_counts = vec![0.0; arr.len()];
+        let mut layouts = 0.0;
+        let mut mines = vec![false; arr.len()];
+        for (tile_id, tile) in arr.iter().enumerate() {
+            mines[tile_id] = tile.flagged();
+        }
+        fn spread(
+            arr: &[Tile], grid: Grid, unknown: &[usize], left: u32, mines: &mut Vec<bool>, mine_counts: &mut [f64], layouts: &mut f64,
+        ) {
+            if left == 0 {
+                let consistent = (0..arr.len()).filter(|&tile_id| arr[tile_id].revealed).all(|tile_id| {
+                    grid.neighbours(tile_id).filter(|&next_id| mines[next_id]).count() == arr[tile_id].adjacent_mines as usize
+                });
+                if consistent {
+                    *layouts += 1.0;
+                    for (count, &mine) in mine_counts.iter_mut().zip(mines.iter()) {
+                        *count += mine as u8 as f64;
+                    }
+                }
+                return;
+            }
+            for (i, &tile_id) in unknown.iter().enumerate() {
+                mines[tile_id] = true;
+                spread(arr, grid, &unknown[i + 1..], left - 1, mines, mine_counts, layouts);
+                mines[tile_id] = false;
+            }
+        }
+        spread(arr, grid, &unknown, total_mines - flags, &mut mines, &mut mine_counts, &mut layouts);
+        mine_counts.iter().map(|count| count / layouts).collect()
+    }
+
+    #[test]
+    fn a_fifty_fifty_is_even() {
+        // a 2 x 2 board with one mine in the top row and the bottom row revealed
+        let grid = Grid::new(2, 2, Topology::Square, false);
+        let mut arr = vec![Tile::default(); 4];
+        arr[0].mines = 1;
+        count_adjacent_mines(&mut arr, grid);
+        arr[2].revealed = true;
+        arr[3].revealed = true;
+        assert_eq!(mine_probabilities(&arr, grid, 1), Some(vec![0.5, 0.5, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn probabilities_match_every_layout_counted_by_hand() {
+        let grids = [
+            Grid::new(4, 4, Topology::Square, false),
+            Grid::new(3, 5, Topology::Square, false),
+            Grid::new(4, 4, Topology::Hex, false),
+            Grid::new(4, 4, Topology::Square, true),
+            Grid::new(4, 5, Topology::Hex, true),
+        ];
+        for grid in grids {
+            for seed in 0..40 {
+                let mines = 3 + (seed % 3) as u32;
+                let mut arr = vec![Tile::default(); grid.tile_count()];
+                let first_click = seed as usize % arr.len();
+                place_mines(&mut arr, mines, 1, first_click, seed);
+                count_adjacent_mines(&mut arr, grid);
+                reveal_tile(&mut arr, first_click, grid);
+                // a second reveal and a correct flag on some boards, for more varied frontiers
+                if let Some(tile_id) = (0..arr.len()).rev().find(|&tile_id| !arr[tile_id].revealed && !arr[tile_id].has_mine()) {
+                    if seed % 2 == 0 {
+                        reveal_tile(&mut arr, tile_id, grid);
+                    }
+                }
+                if seed % 4 == 1 {
+                    let mine = arr.iter().position(|tile| tile.has_mine()).unwrap();
+                    arr[mine].flags = 1;
+                }
+
+                let probabilities = mine_probabilities(&arr, grid, mines).unwrap();
+                for (tile_id, (found, expected)) in probabilities.iter().zip(brute_force(&arr, grid, mines)).enumerate() {
+                    assert!((found - expected).abs() < 1e-12, "{grid:?} seed {seed} tile {tile_id}: {found} != {expected}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Move-by-move recordings of a game.
+//!
+//! Since the mine layout only depends on the seed, the board settings and the
+//! first reveal, a recording only stores those settings and the list of
+//! actions. Replaying the actions on a fresh board rebuilds the game exactly.
+//!
+//! On disk a recording starts with a `rusty-mines-replay <version>` line, the
+//! board settings as `key value` lines and an `actions` line, followed by one
+//! `<seconds> <action> <tile id>` line per action. `undo` and `redo` actions
+//! ignore their tile id and store 0.
+
+use crate::save::SaveError;
+use crate::text::{check_header, parse, BoardSettings, Fields};
+use crate::{Board, BoardOptions};
+
+/// Version written to new recordings. Older versions are rejected rather than
+/// guessed at.
+pub const REPLAY_VERSION: u32 = 1;
+
+const HEADER: &str = "rusty-mines-replay";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    Reveal,
+    Flag,
+    Unflag,
+    Chord,
+    Undo,
+    Redo,
+}
+
+impl ActionKind {
+    fn name(self) -> &'static str {
+        match self {
+            ActionKind::Reveal => "reveal",
+            ActionKind::Flag => "flag",
+            ActionKind::Unflag => "unflag",
+            ActionKind::Chord => "chord",
+            ActionKind::Undo => "undo",
+            ActionKind::Redo => "redo",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "reveal" => Some(ActionKind::Reveal),
+            "flag" => Some(ActionKind::Flag),
+            "unflag" => Some(ActionKind::Unflag),
+            "chord" => Some(ActionKind::Chord),
+            "undo" => Some(ActionKind::Undo),
+            "redo" => Some(ActionKind::Redo),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Action {
+    pub kind: ActionKind,
+    pub tile_id: usize,
+    /// Seconds since the start of the game.
+    pub time: f64,
+}
+
+impl Action {
+    pub fn apply(&self, board: &mut Board) {
+        match self.kind {
+            ActionKind::Reveal => board.reveal(self.tile_id),
+            ActionKind::Chord => board.chord(self.tile_id),
+            ActionKind::Undo => {
+                board.undo();
+            }
+            ActionKind::Redo => {
+                board.redo();
+            }
+            // adds a flag, so only while the tile has room for one
+            ActionKind::Flag => {
+                if board.tile(self.tile_id).flags < board.max_mines_per_tile() {
+                    board.flag(self.tile_id);
+                }
+            }
+            ActionKind::Unflag => {
+                if board.tile(self.tile_id).flagged() {
+                    board.flag(self.tile_id);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    pub rows: u16,
+    pub columns: u16,
+    pub mines: u32,
+    pub seed: u64,
+    pub options: BoardOptions,
+    pub actions: Vec<Action>,
+}
+
+impl Recording {
+    /// Starts an empty recording for a board that has not been played yet.
+    pub fn new(board: &Board) -> Self {
+        Self {
+            rows: board.rows(),
+            columns: board.columns(),
+            mines: board.mines(),
+            seed: board.seed(),
+            options: board.options(),
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, kind: ActionKind, tile_id: usize, time: f64) {
+        self.actions.push(Action { kind, tile_id, time });
+    }
+
+    /// Time of the last action.
+    pub fn duration(&self) -> f64 {
+        self.actions.last().map_or(0.0, |action| action.time)
+    }
+
+    /// A fresh board with the recorded settings.
+    pub fn start_board(&self) -> Board {
+        self.settings().new_board()
+    }
+
+    fn settings(&self) -> BoardSettings {
+        BoardSettings { rows: self.rows, columns: self.columns, mines: self.mines, seed: self.seed, options: self.options }
+    }
+
+    /// The board after the first `count` actions.
+    pub fn board_after(&self, count: usize) -> Board {
+        let mut board = self.start_board();
+        for action in self.actions.iter().take(count) {
+            action.apply(&mut board);
+        }
+        board
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{HEADER} {REPLAY_VERSION}\n");
+        text += &self.settings().to_text();
+        text += "actions\n";
+        for action in &self.actions {
+            text += &format!("{:.3} {} {}\n", action.time, action.kind.name(), action.tile_id);
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, SaveError> {
+        let mut lines = text.lines();
+
+        check_header(lines.next(), HEADER, REPLAY_VERSION)?;
+        let fields = Fields::read(&mut lines, ' ', "actions")?;
+        let BoardSettings { rows, columns, mines, seed, options } = BoardSettings::from_fields(&fields)?;
+
+        let tiles = rows as usize * columns as usize;
+        let mut actions = Vec::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let mut parts = line.split_whitespace();
+            let (Some(time), Some(kind), Some(tile_id), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                return Err(SaveError::Invalid("action", line.to_owned()));
+            };
+            let kind = ActionKind::parse(kind).ok_or_else(|| SaveError::Invalid("action", line.to_owned()))?;
+            let tile_id: usize = parse("tile id", tile_id)?;
+            if tile_id >= tiles {
+                return Err(SaveError::Invalid("tile id", tile_id.to_string()));
+            }
+            actions.push(Action { kind, tile_id, time: parse("action time", time)? });
+        }
+
+        Ok(Recording { rows, columns, mines, seed, options, actions })
+    }
+}
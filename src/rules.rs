@@ -0,0 +1,453 @@
+//! The minesweeper rules, expressed as free functions over a flat tile grid.
+//!
+//! Tiles are stored row by row, so the tile in `row`, `col` has the id
+//! `row * cols + col`. Which tiles are neighbours is up to the [`Grid`].
+
+use std::collections::VecDeque;
+
+use quad_rand::RandGenerator;
+
+use crate::solver::is_solvable_within;
+use crate::topology::Grid;
+use crate::Tile;
+
+/// How many layouts no-guess generation tries before settling for the last one.
+pub const NO_GUESS_ATTEMPTS: u32 = 10_000;
+
+/// How much solving no-guess generation may do, counted in tiles looked at
+/// (see [`is_solvable_within`]), before it settles for a random layout. Every
+/// attempt costs a few times the board size, so large boards get far fewer
+/// attempts than small ones, and the first click takes at most about a second
+/// in a release build even on a 500 x 500 board. Expert boards rarely need
+/// more than a few percent of it.
+pub const NO_GUESS_BUDGET: u64 = 8_000_000;
+
+/// Most mines, and flags, a tile can hold on a multi-mine board.
+pub const MAX_MINES_PER_TILE: u8 = 3;
+
+/// Outcome of a board, as far as the rules are concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardState {
+    Playing,
+    Won,
+    Lost,
+}
+
+/// What it takes to win a board.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WinRule {
+    /// Reveal every tile without a mine. Flags are only reminders.
+    #[default]
+    RevealSafe,
+    /// Flag every mine, with as many flags as mines on each tile, and
+    /// nothing else. A single wrong flag keeps the game going.
+    FlagMines,
+}
+
+/// Adds a flag to an unrevealed tile, or clears its flags once it holds
+/// `max_flags` of them, so with one flag per tile this toggles the flag. A
+/// new flag is only placed while flags remain. Returns true if the tile
+/// changed.
+pub fn flag_tile(arr: &mut [Tile], tile_id: usize, flags_remaining: u32, max_flags: u8) -> bool {
+    let tile = &mut arr[tile_id];
+    if tile.revealed {
+        return false;
+    }
+
+    if tile.flags < max_flags && flags_remaining > 0 {
+        tile.flags += 1;
+        true
+    } else if tile.flagged() {
+        tile.flags = 0;
+        true
+    } else {
+        false
+    }
+}
+
+/// Reveals a tile, flood filling outwards if it has no adjacent mines.
+/// Flagged tiles are left alone. Relies on the numbers from
+/// [`count_adjacent_mines`].
+pub fn reveal_tile(arr: &mut [Tile], tile_id: usize, grid: Grid) {
+    if !arr[tile_id].flagged() {
+        arr[tile_id].revealed = true;
+        reveal_adjacent_tiles(arr, tile_id, grid);
+    }
+}
+
+/// Flood fills outwards from a revealed tile without adjacent mines,
+/// stopping at numbers and flags. The fill works through a queue rather
+/// than recursing, so the size of an opening is only limited by memory.
+pub fn reveal_adjacent_tiles(arr: &mut [Tile], tile_id: usize, grid: Grid) {
+    if arr[tile_id].adjacent_mines != 0 || arr[tile_id].has_mine() {
+        return;
+    }
+
+    let mut openings = VecDeque::from([tile_id]);
+    while let Some(open_id) = openings.pop_front() {
+        for next_id in grid.neighbours(open_id) {
+            let next = &mut arr[next_id];
+            if next.revealed || next.has_mine() || next.flagged() {
+                continue;
+            }
+            next.revealed = true;
+            if next.adjacent_mines == 0 {
+                openings.push_back(next_id);
+            }
+        }
+    }
+}
+
+// chording action - both mouse buttons pressed on a revealed tile with a number equal to flagged adjacent cells
+pub fn reveal_all_adjacent_tiles(arr: &mut [Tile], tile_id: usize, grid: Grid) {
+    if !arr[tile_id].revealed {
+        return;
+    }
+
+    // count flags on adjacent cells
+    let flags: u32 = grid.neighbours(tile_id).map(|next_id| arr[next_id].flags as u32).sum();
+
+    // check if number of adjacent flags = number of adjacent mines
+    if flags == arr[tile_id].adjacent_mines as u32 {
+        for next_id in grid.neighbours(tile_id) {
+            if !arr[next_id].revealed {
+                reveal_tile(arr, next_id, grid);
+            }
+        }
+    }
+}
+
+pub fn num_adjacent_mines(arr: &[Tile], grid: Grid, tile_id: usize) -> u8 {
+    grid.neighbours(tile_id).map(|next_id| arr[next_id].mines).sum()
+}
+
+/// Works out the number of every tile, hidden or not, once the mines are in
+/// place, so revealing never has to count.
+pub fn count_adjacent_mines(arr: &mut [Tile], grid: Grid) {
+    for tile_id in 0..arr.len() {
+        arr[tile_id].adjacent_mines = num_adjacent_mines(arr, grid, tile_id);
+    }
+}
+
+pub fn initialize_grid(arr: &mut [Tile]) {
+    for tile in arr {
+        *tile = Tile::default();
+    }
+}
+
+fn seeded_rng(seed: u64) -> RandGenerator {
+    let rng = RandGenerator::new();
+    rng.srand(seed);
+    rng
+}
+
+/// Scatters `num_mines` mines over the grid, at most `max_per_tile` on a
+/// tile and never on `exclude_tile_id`. The layout only depends on the seed,
+/// the grid size, the mine counts and the excluded tile.
+pub fn place_mines(arr: &mut [Tile], num_mines: u32, max_per_tile: u8, exclude_tile_id: usize, seed: u64) {
+    scatter_mines(arr, num_mines, max_per_tile, &[exclude_tile_id], &seeded_rng(seed));
+}
+
+/// Lays out mines so that the whole board can be cleared from `first_click`
+/// by logic alone. The first click and its neighbours stay free of mines when
+/// there is room, so the game opens with a cascade.
+///
+/// Layouts are re-rolled up to [`NO_GUESS_ATTEMPTS`] times from the same
+/// seeded sequence, within [`NO_GUESS_BUDGET`], so the result is as
+/// reproducible as [`place_mines`]. Returns false if none of them worked in
+/// time, in which case the last, random, layout is kept.
+/// The solver only knows one mine per tile, so this lays out classic boards.
+pub fn place_mines_no_guess(arr: &mut [Tile], num_mines: u32, grid: Grid, first_click: usize, seed: u64) -> bool {
+    place_mines_no_guess_within(arr, num_mines, grid, first_click, seed, NO_GUESS_BUDGET)
+}
+
+// `place_mines_no_guess` with a budget of its own
+fn place_mines_no_guess_within(arr: &mut [Tile], num_mines: u32, grid: Grid, first_click: usize, seed: u64, mut budget: u64) -> bool {
+    let rng = seeded_rng(seed);
+    let mut excluded: Vec<usize> = vec![first_click];
+    excluded.extend(grid.neighbours(first_click));
+    if arr.len() - excluded.len() < num_mines as usize {
+        excluded.truncate(1);
+    }
+
+    for _ in 0..NO_GUESS_ATTEMPTS {
+        for tile in arr.iter_mut() {
+            tile.mines = 0;
+        }
+        scatter_mines(arr, num_mines, 1, &excluded, &rng);
+        match is_solvable_within(arr, grid, num_mines, first_click, &mut budget) {
+            Some(true) => return true,
+            Some(false) => (),
+            None => return false,
+        }
+    }
+    false
+}
+
+fn scatter_mines(arr: &mut [Tile], num_mines: u32, max_per_tile: u8, excluded: &[usize], rng: &RandGenerator) {
+    let mut placed_mines: u32 = 0;
+
+    while placed_mines < num_mines {
+        let n: usize = rng.gen_range(0, arr.len());
+        if arr[n].mines >= max_per_tile || excluded.contains(&n) {
+            continue;
+        }
+        arr[n].mines += 1;
+        placed_mines += 1;
+    }
+}
+
+/// Ends a game in progress: lost as soon as a mine is revealed, won once
+/// `rule` is met.
+pub fn update_game_state(arr: &[Tile], state: &mut BoardState, rule: WinRule) {
+    if *state != BoardState::Playing {
+        return;
+    }
+
+    if arr.iter().any(|tile| tile.revealed && tile.has_mine()) {
+        *state = BoardState::Lost;
+        return;
+    }
+
+    let won = match rule {
+        WinRule::RevealSafe => arr.iter().all(|tile| tile.revealed || tile.has_mine()),
+        WinRule::FlagMines => arr.iter().all(|tile| tile.flags == tile.mines),
+    };
+    if won {
+        *state = BoardState::Won;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Layout;
+    use crate::Topology;
+
+    // tiles and grid from `*` / `.` rows
+    fn board(text: &str) -> (Vec<Tile>, Grid) {
+        let layout = Layout::from_text(text).unwrap();
+        let mut tiles: Vec<Tile> = layout.mines.iter().map(|&mine| Tile { mines: mine as u8, ..Tile::default() }).collect();
+        let grid = Grid::new(layout.rows, layout.columns, Topology::Square, false);
+        count_adjacent_mines(&mut tiles, grid);
+        (tiles, grid)
+    }
+
+    fn revealed(arr: &[Tile]) -> Vec<usize> {
+        (0..arr.len()).filter(|&tile_id| arr[tile_id].revealed).collect()
+    }
+
+    fn state_after(arr: &[Tile], rule: WinRule) -> BoardState {
+        let mut state = BoardState::Playing;
+        update_game_state(arr, &mut state, rule);
+        state
+    }
+
+    #[test]
+    fn revealing_every_safe_tile_wins() {
+        let (mut arr, grid) = board("*..\n...\n..*\n");
+        for tile_id in [1, 2, 3, 4, 5, 6, 7] {
+            reveal_tile(&mut arr, tile_id, grid);
+        }
+        assert_eq!(state_after(&arr, WinRule::RevealSafe), BoardState::Won);
+        assert_eq!(state_after(&arr, WinRule::FlagMines), BoardState::Playing);
+    }
+
+    #[test]
+    fn one_hidden_safe_tile_keeps_the_game_going() {
+        let (mut arr, grid) = board("*..\n...\n..*\n");
+        for tile_id in [1, 2, 3, 4, 5, 7] {
+            reveal_tile(&mut arr, tile_id, grid);
+        }
+        assert!(!arr[6].revealed);
+        assert_eq!(state_after(&arr, WinRule::RevealSafe), BoardState::Playing);
+    }
+
+    #[test]
+    fn a_revealed_mine_loses_under_either_rule() {
+        let (mut arr, grid) = board("*..\n...\n..*\n");
+        arr[8].flags = 1;
+        reveal_tile(&mut arr, 0, grid);
+        assert_eq!(state_after(&arr, WinRule::RevealSafe), BoardState::Lost);
+        assert_eq!(state_after(&arr, WinRule::FlagMines), BoardState::Lost);
+    }
+
+    #[test]
+    fn flags_alone_dont_win_the_reveal_rule() {
+        let (mut arr, _) = board("*..\n...\n..*\n");
+        arr[0].flags = 1;
+        arr[8].flags = 1;
+        assert_eq!(state_after(&arr, WinRule::RevealSafe), BoardState::Playing);
+        assert_eq!(state_after(&arr, WinRule::FlagMines), BoardState::Won);
+    }
+
+    #[test]
+    fn a_wrong_flag_blocks_the_flag_rule() {
+        let (mut arr, _) = board("*..\n...\n..*\n");
+        arr[0].flags = 1;
+        arr[8].flags = 1;
+        arr[4].flags = 1;
+        assert_eq!(state_after(&arr, WinRule::FlagMines), BoardState::Playing);
+    }
+
+    #[test]
+    fn a_missing_flag_blocks_the_flag_rule() {
+        let (mut arr, _) = board("*..\n...\n..*\n");
+        arr[0].flags = 1;
+        assert_eq!(state_after(&arr, WinRule::FlagMines), BoardState::Playing);
+    }
+
+    #[test]
+    fn stacked_mines_need_one_flag_each() {
+        let (mut arr, _) = board("*.\n..\n");
+        arr[0].mines = 2;
+        arr[0].flags = 1;
+        assert_eq!(state_after(&arr, WinRule::FlagMines), BoardState::Playing);
+        arr[0].flags = 2;
+        assert_eq!(state_after(&arr, WinRule::FlagMines), BoardState::Won);
+        arr[0].flags = 3;
+        assert_eq!(state_after(&arr, WinRule::FlagMines), BoardState::Playing);
+    }
+
+    #[test]
+    fn a_finished_game_stays_finished() {
+        let (mut arr, grid) = board("*.\n..\n");
+        reveal_tile(&mut arr, 0, grid);
+        let mut state = BoardState::Won;
+        update_game_state(&arr, &mut state, WinRule::RevealSafe);
+        assert_eq!(state, BoardState::Won);
+    }
+
+    #[test]
+    fn revealing_a_number_reveals_only_that_tile() {
+        let (mut arr, grid) = board("*...\n....\n....\n");
+        reveal_tile(&mut arr, 1, grid);
+        assert_eq!(revealed(&arr), [1]);
+        assert_eq!(arr[1].adjacent_mines, 1);
+    }
+
+    #[test]
+    fn an_opening_floods_up_to_its_numbers() {
+        let (mut arr, grid) = board("*...\n....\n....\n");
+        reveal_tile(&mut arr, 11, grid);
+        assert_eq!(revealed(&arr), (1..12).collect::<Vec<_>>());
+        assert_eq!(arr[5].adjacent_mines, 1);
+        assert_eq!(arr[11].adjacent_mines, 0);
+    }
+
+    #[test]
+    fn the_flood_fill_stops_at_a_wall_of_mines() {
+        let (mut arr, grid) = board("..*..\n..*..\n..*..\n");
+        reveal_tile(&mut arr, 0, grid);
+        assert_eq!(revealed(&arr), [0, 1, 5, 6, 10, 11]);
+    }
+
+    #[test]
+    fn the_flood_fill_stops_at_flagged_tiles() {
+        let (mut arr, grid) = board("....\n....\n...*\n");
+        arr[2].flags = 1;
+        reveal_tile(&mut arr, 0, grid);
+        // the opening behind the flag stays closed
+        assert_eq!(revealed(&arr), [0, 1, 4, 5, 6, 8, 9, 10]);
+        // and the flag itself can't be revealed
+        reveal_tile(&mut arr, 2, grid);
+        assert!(!arr[2].revealed);
+    }
+
+    #[test]
+    fn the_flood_fill_crosses_wrapped_edges() {
+        let (mut arr, _) = board("....\n.*..\n....\n....\n");
+        let grid = Grid::new(4, 4, Topology::Square, true);
+        count_adjacent_mines(&mut arr, grid);
+        reveal_tile(&mut arr, 15, grid);
+        // the wrapped opening goes round the mine's 3x3 block
+        assert_eq!(revealed(&arr).len(), 15);
+        assert_eq!(arr[0].adjacent_mines, 1);
+    }
+
+    #[test]
+    fn a_huge_opening_doesnt_overflow_the_stack() {
+        let grid = Grid::new(1000, 1000, Topology::Square, false);
+        let mut arr = vec![Tile::default(); grid.tile_count()];
+        reveal_tile(&mut arr, 0, grid);
+        assert!(arr.iter().all(|tile| tile.revealed));
+    }
+
+    #[test]
+    fn no_guess_generation_gives_up_when_the_budget_runs_out() {
+        // a 100 x 100 board gets the share of the budget a 500 x 500 one would
+        // leave each tile, densely mined and at a density that never finished before
+        let grid = Grid::new(100, 100, Topology::Square, false);
+        let budget = NO_GUESS_BUDGET * 100 * 100 / (500 * 500);
+        for mines in [8000, 2000] {
+            let mut arr = vec![Tile::default(); grid.tile_count()];
+            assert!(!place_mines_no_guess_within(&mut arr, mines, grid, 0, 7, budget));
+            assert_eq!(arr.iter().map(|tile| tile.mines as u32).sum::<u32>(), mines);
+            assert!(!arr[0].has_mine());
+        }
+    }
+
+    #[test]
+    fn no_guess_generation_still_finds_expert_boards() {
+        let grid = Grid::new(16, 30, Topology::Square, false);
+        let mut arr = vec![Tile::default(); grid.tile_count()];
+        assert!(place_mines_no_guess(&mut arr, 99, grid, 0, 7));
+    }
+
+    #[test]
+    fn chording_with_matching_flags_reveals_the_neighbours() {
+        let (mut arr, grid) = board("*..\n...\n...\n");
+        reveal_tile(&mut arr, 4, grid);
+        arr[0].flags = 1;
+        reveal_all_adjacent_tiles(&mut arr, 4, grid);
+        assert_eq!(revealed(&arr), (1..9).collect::<Vec<_>>());
+        assert_eq!(state_after(&arr, WinRule::RevealSafe), BoardState::Won);
+    }
+
+    #[test]
+    fn chording_without_enough_flags_does_nothing() {
+        let (mut arr, grid) = board("*..\n...\n..*\n");
+        reveal_tile(&mut arr, 4, grid);
+        arr[0].flags = 1;
+        reveal_all_adjacent_tiles(&mut arr, 4, grid);
+        assert_eq!(revealed(&arr), [4]);
+    }
+
+    #[test]
+    fn chording_a_hidden_tile_does_nothing() {
+        let (mut arr, grid) = board("*..\n...\n...\n");
+        arr[0].flags = 1;
+        reveal_all_adjacent_tiles(&mut arr, 4, grid);
+        assert!(revealed(&arr).is_empty());
+    }
+
+    #[test]
+    fn chording_around_a_wrong_flag_hits_the_mine() {
+        let (mut arr, grid) = board("*..\n...\n...\n");
+        reveal_tile(&mut arr, 4, grid);
+        arr[1].flags = 1;
+        reveal_all_adjacent_tiles(&mut arr, 4, grid);
+        assert!(arr[0].revealed);
+        assert_eq!(state_after(&arr, WinRule::RevealSafe), BoardState::Lost);
+    }
+
+    #[test]
+    fn flags_cycle_up_to_the_limit_and_clear() {
+        let (mut arr, _) = board("*.\n..\n");
+        assert!(flag_tile(&mut arr, 0, 3, 3));
+        assert!(flag_tile(&mut arr, 0, 2, 3));
+        assert!(flag_tile(&mut arr, 0, 1, 3));
+        assert_eq!(arr[0].flags, 3);
+        assert!(flag_tile(&mut arr, 0, 0, 3));
+        assert_eq!(arr[0].flags, 0);
+    }
+
+    #[test]
+    fn no_flag_is_placed_once_they_run_out() {
+        let (mut arr, grid) = board("*.\n..\n");
+        assert!(!flag_tile(&mut arr, 1, 0, 1));
+        reveal_tile(&mut arr, 3, grid);
+        assert!(!flag_tile(&mut arr, 3, 1, 1));
+        assert!(!arr[1].flagged() && !arr[3].flagged());
+    }
+}
@@ -0,0 +1,246 @@
+//! Plain-text save files for games in progress.
+//!
+//! A save starts with a `rusty-mines-save <version>` line, followed by one
+//! `key value` line per field and a `tiles` line. After that comes one line
+//! per grid row with one character per tile:
+//!
+//! | char  | tile                                |
+//! |-------|-------------------------------------|
+//! | `.`   | hidden, no mine                     |
+//! | `*`   | hidden mine                         |
+//! | `f`   | flagged, no mine                    |
+//! | `F`   | flagged mine                        |
+//! | `0-9` | revealed, with its adjacent mines   |
+//! | `+`   | revealed, 10 or more adjacent mines |
+//! | `X`   | revealed mine                       |
+//!
+//! Multi-mine boards add a `counts` line after the tiles, then one line per
+//! grid row with two digits per tile: its mines, then its flags. The
+//! numbers of all tiles are worked out again from the mines on loading.
+
+use std::fmt;
+
+use crate::metrics::{three_bv, Clicks};
+use crate::rules::{count_adjacent_mines, MAX_MINES_PER_TILE};
+use crate::text::{check_header, parse, BoardSettings, Fields};
+use crate::{Board, BoardState, Tile};
+
+/// Version written to new saves. Older versions are rejected rather than
+/// guessed at.
+pub const SAVE_VERSION: u32 = 1;
+
+const HEADER: &str = "rusty-mines-save";
+
+/// A board together with how long it has been played.
+#[derive(Debug, Clone)]
+pub struct SavedGame {
+    pub board: Board,
+    /// Seconds spent on the board so far.
+    pub elapsed: f64,
+}
+
+/// Problems reading a save file, a recording or a board layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveError {
+    UnsupportedVersion(String),
+    Missing(&'static str),
+    Invalid(&'static str, String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::UnsupportedVersion(version) => write!(f, "unsupported save version {version}"),
+            SaveError::Missing(field) => write!(f, "missing {field}"),
+            SaveError::Invalid(field, value) => write!(f, "invalid {field}: {value:?}"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl SavedGame {
+    pub fn to_text(&self) -> String {
+        let board = &self.board;
+        let mut text = format!("{HEADER} {SAVE_VERSION}\n");
+        text += &BoardSettings::of(board).to_text();
+        text += &format!("state {}\n", state_name(board.state));
+        text += &format!("mines_placed {}\n", board.mines_placed);
+        text += &format!("guess_free {}\n", board.guess_free);
+        text += &format!("hints_used {}\n", board.hints_used);
+        text += &format!("clicks {} {} {}\n", board.clicks.left, board.clicks.right, board.clicks.chord);
+        text += &format!("practice {}\n", board.practice);
+        text += &format!("elapsed {}\n", self.elapsed);
+        text += "tiles\n";
+        for row in board.tiles.chunks(board.columns as usize) {
+            text.extend(row.iter().map(tile_char));
+            text.push('\n');
+        }
+        if board.options.multi_mine {
+            text += "counts\n";
+            for row in board.tiles.chunks(board.columns as usize) {
+                for tile in row {
+                    text.push(char::from_digit(tile.mines as u32, 10).unwrap_or('0'));
+                    text.push(char::from_digit(tile.flags as u32, 10).unwrap_or('0'));
+                }
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, SaveError> {
+        let mut lines = text.lines();
+        check_header(lines.next(), HEADER, SAVE_VERSION)?;
+        let fields = Fields::read(&mut lines, ' ', "tiles")?;
+
+        let settings = BoardSettings::from_fields(&fields)?;
+        let BoardSettings { rows, columns, mines, .. } = settings;
+        let multi_mine = settings.options.multi_mine;
+        let mut board = settings.new_board();
+        board.state = match fields.get("state")? {
+            "playing" => BoardState::Playing,
+            "won" => BoardState::Won,
+            "lost" => BoardState::Lost,
+            other => return Err(SaveError::Invalid("state", other.to_owned())),
+        };
+        board.mines_placed = fields.parse("mines_placed")?;
+        board.guess_free = fields.parse("guess_free")?;
+        board.hints_used = fields.parse("hints_used")?;
+        let clicks = fields.get("clicks")?;
+        let counts: Vec<u32> = clicks.split(' ').map(|n| parse("clicks", n)).collect::<Result<_, _>>()?;
+        let [left, right, chord] = counts[..] else {
+            return Err(SaveError::Invalid("clicks", clicks.to_owned()));
+        };
+        board.clicks = Clicks { left, right, chord };
+        board.practice = fields.parse("practice")?;
+        let elapsed: f64 = fields.parse("elapsed")?;
+
+        let mut tiles = Vec::with_capacity(board.tiles.len());
+        for line in lines.by_ref().take(rows as usize) {
+            if line.chars().count() != columns as usize {
+                return Err(SaveError::Invalid("tile row", line.to_owned()));
+            }
+            for c in line.chars() {
+                tiles.push(parse_tile(c).ok_or_else(|| SaveError::Invalid("tile", c.to_string()))?);
+            }
+        }
+        if tiles.len() != board.tiles.len() {
+            return Err(SaveError::Missing("tile rows"));
+        }
+        if multi_mine {
+            if lines.next() != Some("counts") {
+                return Err(SaveError::Missing("counts"));
+            }
+            parse_counts(&mut tiles, lines.take(rows as usize), columns)?;
+        }
+        if tiles.iter().map(|tile| tile.mines as u32).sum::<u32>() != mines && board.mines_placed {
+            return Err(SaveError::Invalid("mine layout", "wrong number of mines".to_owned()));
+        }
+        board.mines_flagged = tiles.iter().map(|tile| tile.flags as u32).sum();
+        if board.mines_flagged > mines {
+            return Err(SaveError::Invalid("flags", board.mines_flagged.to_string()));
+        }
+        board.tiles = tiles;
+        if board.mines_placed {
+            // the numbers of hidden tiles aren't saved, count them all again
+            let grid = board.grid();
+            count_adjacent_mines(&mut board.tiles, grid);
+            board.three_bv = three_bv(&board.tiles, grid);
+        }
+
+        Ok(SavedGame { board, elapsed })
+    }
+}
+
+fn state_name(state: BoardState) -> &'static str {
+    match state {
+        BoardState::Playing => "playing",
+        BoardState::Won => "won",
+        BoardState::Lost => "lost",
+    }
+}
+
+// mine and flag counts of multi-mine boards, which must agree with the tile characters
+fn parse_counts<'a>(tiles: &mut [Tile], lines: impl Iterator<Item = &'a str>, columns: u16) -> Result<(), SaveError> {
+    let mut tile_id = 0;
+    for line in lines {
+        let digits: Vec<u8> = line.chars().map(|c| c.to_digit(10).map(|n| n as u8)).collect::<Option<_>>().unwrap_or_default();
+        if digits.len() != 2 * columns as usize || digits.iter().any(|&n| n > MAX_MINES_PER_TILE) {
+            return Err(SaveError::Invalid("counts row", line.to_owned()));
+        }
+        for pair in digits.chunks(2) {
+            let tile = tiles.get_mut(tile_id).ok_or(SaveError::Invalid("counts row", line.to_owned()))?;
+            if (pair[0] > 0) != tile.has_mine() || (pair[1] > 0) != tile.flagged() {
+                return Err(SaveError::Invalid("counts", format!("tile {tile_id}")));
+            }
+            tile.mines = pair[0];
+            tile.flags = pair[1];
+            tile_id += 1;
+        }
+    }
+    if tile_id != tiles.len() {
+        return Err(SaveError::Missing("counts rows"));
+    }
+    Ok(())
+}
+
+fn tile_char(tile: &Tile) -> char {
+    match (tile.revealed, tile.flagged(), tile.has_mine()) {
+        (true, _, true) => 'X',
+        (true, _, false) => char::from_digit(tile.adjacent_mines as u32, 10).unwrap_or('+'),
+        (false, true, true) => 'F',
+        (false, true, false) => 'f',
+        (false, false, true) => '*',
+        (false, false, false) => '.',
+    }
+}
+
+fn parse_tile(c: char) -> Option<Tile> {
+    let tile = match c {
+        'X' => Tile { revealed: true, mines: 1, ..Tile::default() },
+        'F' => Tile { flags: 1, mines: 1, ..Tile::default() },
+        'f' => Tile { flags: 1, ..Tile::default() },
+        '*' => Tile { mines: 1, ..Tile::default() },
+        '.' => Tile::default(),
+        '0'..='9' => Tile { revealed: true, adjacent_mines: c.to_digit(10)? as u8, ..Tile::default() },
+        // only on multi-mine boards, whose numbers are counted again after loading
+        '+' => Tile { revealed: true, ..Tile::default() },
+        _ => return None,
+    };
+    Some(tile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::{ActionKind, Recording};
+    use crate::{BoardOptions, Topology};
+
+    #[test]
+    fn a_game_in_progress_reads_back_as_it_was_written() {
+        let options = BoardOptions { topology: Topology::Hex, wrap: true, ..Default::default() };
+        let mut board = Board::new(9, 9, 10, 5, options);
+        board.reveal(40);
+        board.flag(board.tiles().iter().position(|tile| !tile.revealed).unwrap());
+        let game = SavedGame { board, elapsed: 12.5 };
+        let text = game.to_text();
+        assert_eq!(SavedGame::from_text(&text).unwrap().to_text(), text);
+    }
+
+    #[test]
+    fn a_recording_reads_back_as_it_was_written() {
+        let mut recording = Recording::new(&Board::new(8, 8, 10, 3, BoardOptions::default()));
+        recording.record(ActionKind::Reveal, 27, 0.5);
+        recording.record(ActionKind::Flag, 0, 1.25);
+        recording.record(ActionKind::Undo, 0, 2.0);
+        assert_eq!(Recording::from_text(&recording.to_text()), Ok(recording));
+    }
+
+    #[test]
+    fn saves_missing_a_field_are_rejected() {
+        let text = SavedGame { board: Board::new(8, 8, 10, 1, BoardOptions::default()), elapsed: 0.0 }.to_text();
+        let without_practice: String = text.lines().filter(|line| !line.starts_with("practice")).map(|line| format!("{line}\n")).collect();
+        assert_eq!(SavedGame::from_text(&without_practice).unwrap_err(), SaveError::Missing("practice"));
+    }
+}
@@ -0,0 +1,95 @@
+// Choices made on the settings screen, kept as `key value` lines. Lines that
+// aren't understood are skipped, so a file from another version still loads.
+
+use crate::theme::ColorVision;
+
+// how far the interface can be scaled up or down
+pub const UI_SCALES:[f32; 5] = [0.75, 1.0, 1.25, 1.5, 2.0];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    // name of the theme, the default one if empty or no longer there
+    pub theme:String,
+    pub color_vision:ColorVision,
+    // thicker lines around every tile
+    pub bold_outlines:bool,
+    // hatching on hidden tiles, so they differ from revealed ones by more than colour
+    pub patterned_tiles:bool,
+    // numbers written with the font instead of the sprites
+    pub text_numbers:bool,
+    // size of the menus, the top panel and messages, one of `UI_SCALES`
+    pub ui_scale:f32,
+    // from 0 to 1, the sound effects play at both multiplied
+    pub master_volume:f32,
+    pub effects_volume:f32,
+    // silences everything without losing the volumes, M switches it
+    pub muted:bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: String::new(),
+            color_vision: ColorVision::Normal,
+            bold_outlines: false,
+            patterned_tiles: false,
+            text_numbers: false,
+            ui_scale: 1.0,
+            master_volume: 0.8,
+            effects_volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn from_text(text:&str) -> Self {
+        let mut settings = Self::default();
+        for line in text.lines() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "theme" => settings.theme = value.to_owned(),
+                "color_vision" => settings.color_vision = ColorVision::from_name(value).unwrap_or_default(),
+                "bold_outlines" => settings.bold_outlines = value == "true",
+                "patterned_tiles" => settings.patterned_tiles = value == "true",
+                "text_numbers" => settings.text_numbers = value == "true",
+                "ui_scale" => {
+                    if let Some(scale) = value.parse::<f32>().ok().filter(|scale| UI_SCALES.contains(scale)) {
+                        settings.ui_scale = scale;
+                    }
+                }
+                "master_volume" => settings.master_volume = parse_volume(value).unwrap_or(settings.master_volume),
+                "effects_volume" => settings.effects_volume = parse_volume(value).unwrap_or(settings.effects_volume),
+                "muted" => settings.muted = value == "true",
+                _ => (),
+            }
+        }
+        settings
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("theme {}\n", self.theme);
+        text += &format!("color_vision {}\n", self.color_vision.name());
+        text += &format!("bold_outlines {}\n", self.bold_outlines);
+        text += &format!("patterned_tiles {}\n", self.patterned_tiles);
+        text += &format!("text_numbers {}\n", self.text_numbers);
+        text += &format!("ui_scale {}\n", self.ui_scale);
+        text += &format!("master_volume {}\n", self.master_volume);
+        text += &format!("effects_volume {}\n", self.effects_volume);
+        text += &format!("muted {}\n", self.muted);
+        text
+    }
+
+    // what the sound effects play at
+    pub fn sound_volume(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master_volume * self.effects_volume
+        }
+    }
+}
+
+fn parse_volume(text:&str) -> Option<f32> {
+    text.parse::<f32>().ok().filter(|volume| (0.0..=1.0).contains(volume))
+}
@@ -0,0 +1,283 @@
+//! Logical solver working only from what a player can see: the numbers on
+//! revealed tiles and the flags. Flags are trusted as mines.
+//!
+//! Every revealed number gives a constraint "exactly `n` of these unknown
+//! tiles are mines". The solver applies single-point reasoning to each
+//! constraint on its own, and subset reasoning to pairs where one
+//! constraint's tiles are contained in another's. The total mine count is
+//! used as one more constraint over all unknown tiles.
+//!
+//! The reasoning assumes at most one mine per tile, so it only applies to
+//! classic boards, not multi-mine ones.
+
+use std::collections::HashMap;
+
+use crate::rules::{count_adjacent_mines, reveal_tile};
+use crate::topology::Grid;
+use crate::Tile;
+
+/// Tiles that are provably safe or provably mined, in ascending tile order.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Deductions {
+    pub safe: Vec<usize>,
+    pub mines: Vec<usize>,
+}
+
+impl Deductions {
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.mines.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Constraint {
+    tiles: Vec<usize>,
+    mines: i32,
+}
+
+/// Finds every tile that can be proven safe or mined from the visible state
+/// of the grid.
+pub fn deduce(arr: &[Tile], grid: Grid, total_mines: u32) -> Deductions {
+    let mut budget = u64::MAX;
+    deduce_within(arr, grid, total_mines, &mut budget).unwrap_or_default()
+}
+
+// `deduce`, giving up with None once `budget` runs out. Every pass over the
+// constraints costs the number of tiles plus the tiles in all constraints.
+fn deduce_within(arr: &[Tile], grid: Grid, total_mines: u32, budget: &mut u64) -> Option<Deductions> {
+    // None = unknown, Some(true) = mine, Some(false) = safe
+    let mut known: Vec<Option<bool>> = arr
+        .iter()
+        .map(|tile| {
+            if tile.flagged() {
+                Some(true)
+            } else if tile.revealed {
+                Some(false)
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let mut deductions = Deductions::default();
+
+    loop {
+        let constraints = build_constraints(arr, &known, grid, total_mines);
+        let cost = arr.len() + constraints.iter().map(|constraint| constraint.tiles.len()).sum::<usize>();
+        *budget = budget.checked_sub(cost as u64)?;
+        let mut progress = false;
+
+        // single-point reasoning
+        for constraint in &constraints {
+            if constraint.mines == 0 {
+                progress |= mark(&constraint.tiles, false, &mut known, &mut deductions);
+            } else if constraint.mines == constraint.tiles.len() as i32 {
+                progress |= mark(&constraint.tiles, true, &mut known, &mut deductions);
+            }
+        }
+
+        // subset reasoning, only comparing constraints that share a tile
+        if !progress {
+            let mut by_tile: HashMap<usize, Vec<usize>> = HashMap::new();
+            for (i, constraint) in constraints.iter().enumerate() {
+                for &tile_id in &constraint.tiles {
+                    by_tile.entry(tile_id).or_default().push(i);
+                }
+            }
+
+            for small in &constraints {
+                let Some(candidates) = small.tiles.first().and_then(|tile_id| by_tile.get(tile_id)) else {
+                    continue;
+                };
+                for &j in candidates {
+                    let large = &constraints[j];
+                    if large.tiles.len() <= small.tiles.len() || !is_subset(&small.tiles, &large.tiles) {
+                        continue;
+                    }
+                    let rest: Vec<usize> = large.tiles.iter().copied().filter(|t| small.tiles.binary_search(t).is_err()).collect();
+                    let rest_mines = large.mines - small.mines;
+                    if rest_mines == 0 {
+                        progress |= mark(&rest, false, &mut known, &mut deductions);
+                    } else if rest_mines == rest.len() as i32 {
+                        progress |= mark(&rest, true, &mut known, &mut deductions);
+                    }
+                }
+            }
+        }
+
+        if !progress {
+            break;
+        }
+    }
+
+    deductions.safe.sort_unstable();
+    deductions.mines.sort_unstable();
+    Some(deductions)
+}
+
+// records newly proven tiles, returns true if anything was learned
+fn mark(tiles: &[usize], mine: bool, known: &mut [Option<bool>], deductions: &mut Deductions) -> bool {
+    let mut progress = false;
+    for &tile_id in tiles {
+        if known[tile_id].is_none() {
+            known[tile_id] = Some(mine);
+            if mine {
+                deductions.mines.push(tile_id);
+            } else {
+                deductions.safe.push(tile_id);
+            }
+            progress = true;
+        }
+    }
+    progress
+}
+
+fn build_constraints(arr: &[Tile], known: &[Option<bool>], grid: Grid, total_mines: u32) -> Vec<Constraint> {
+    let mut constraints: Vec<Constraint> = Vec::new();
+
+    for (tile_id, tile) in arr.iter().enumerate() {
+        if !tile.revealed || tile.has_mine() {
+            continue;
+        }
+        let mut tiles = Vec::new();
+        let mut mines = tile.adjacent_mines as i32;
+        for next_id in grid.neighbours(tile_id) {
+            match known[next_id] {
+                None => tiles.push(next_id),
+                Some(true) => mines -= 1,
+                Some(false) => (),
+            }
+        }
+        // a negative count means a wrong flag, which proves nothing
+        if !tiles.is_empty() && mines >= 0 && mines <= tiles.len() as i32 {
+            constraints.push(Constraint { tiles, mines });
+        }
+    }
+
+    let unknown: Vec<usize> = (0..arr.len()).filter(|&tile_id| known[tile_id].is_none()).collect();
+    let mines_left = total_mines as i32 - known.iter().filter(|k| **k == Some(true)).count() as i32;
+    if !unknown.is_empty() && mines_left >= 0 && mines_left <= unknown.len() as i32 {
+        constraints.push(Constraint { tiles: unknown, mines: mines_left });
+    }
+
+    constraints.sort_unstable_by(|a, b| a.tiles.cmp(&b.tiles));
+    constraints.dedup();
+    constraints
+}
+
+// both slices are sorted
+fn is_subset(small: &[usize], large: &[usize]) -> bool {
+    small.iter().all(|t| large.binary_search(t).is_ok())
+}
+
+/// Plays the board from `first_click` using only logical deductions and
+/// reports whether every safe tile gets revealed without guessing.
+pub fn is_solvable(arr: &[Tile], grid: Grid, total_mines: u32, first_click: usize) -> bool {
+    let mut budget = u64::MAX;
+    is_solvable_within(arr, grid, total_mines, first_click, &mut budget) == Some(true)
+}
+
+/// Like [`is_solvable`], but gives up with None once `budget` runs out. It is
+/// spent on the solver's passes over the grid, each costing the number of tiles
+/// plus the tiles of every constraint, so it tracks the time taken on any board.
+/// What was spent is taken off `budget`, so several calls can share it.
+pub fn is_solvable_within(arr: &[Tile], grid: Grid, total_mines: u32, first_click: usize, budget: &mut u64) -> Option<bool> {
+    if arr[first_click].has_mine() {
+        return Some(false);
+    }
+    // setting up the visible board looks at every tile a few times
+    *budget = budget.checked_sub(4 * arr.len() as u64)?;
+
+    let mut visible: Vec<Tile> = arr.iter().map(|tile| Tile { mines: tile.mines, ..Tile::default() }).collect();
+    count_adjacent_mines(&mut visible, grid);
+    reveal_tile(&mut visible, first_click, grid);
+
+    loop {
+        let deductions = deduce_within(&visible, grid, total_mines, budget)?;
+        if deductions.is_empty() {
+            break;
+        }
+        for tile_id in deductions.mines {
+            visible[tile_id].flags = 1;
+        }
+        for tile_id in deductions.safe {
+            if !visible[tile_id].revealed {
+                reveal_tile(&mut visible, tile_id, grid);
+            }
+        }
+    }
+
+    Some(visible.iter().all(|tile| tile.revealed || tile.has_mine()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Layout;
+    use crate::Topology;
+
+    // tiles and grid from `*` / `.` rows, with the tiles in `revealed` uncovered
+    fn board(text: &str, revealed: &[usize]) -> (Vec<Tile>, Grid) {
+        let layout = Layout::from_text(text).unwrap();
+        let mut tiles: Vec<Tile> = layout.mines.iter().map(|&mine| Tile { mines: mine as u8, ..Tile::default() }).collect();
+        let grid = Grid::new(layout.rows, layout.columns, Topology::Square, false);
+        count_adjacent_mines(&mut tiles, grid);
+        for &tile_id in revealed {
+            tiles[tile_id].revealed = true;
+        }
+        (tiles, grid)
+    }
+
+    #[test]
+    fn a_one_two_one_has_mines_under_the_ones() {
+        // hidden top row over a revealed 1 2 1
+        let (arr, grid) = board("*.*\n...\n", &[3, 4, 5]);
+        let deductions = deduce(&arr, grid, 2);
+        assert_eq!(deductions, Deductions { safe: vec![1], mines: vec![0, 2] });
+    }
+
+    #[test]
+    fn subsets_clear_the_rest_of_a_larger_constraint() {
+        // a revealed 1 1 2 1 1 under a hidden row: {0,1} = 1 inside {0,1,2} = 1 clears 2
+        let (arr, grid) = board(".*.*.\n.....\n", &[5, 6, 7, 8, 9]);
+        let deductions = deduce(&arr, grid, 2);
+        assert_eq!(deductions, Deductions { safe: vec![0, 2, 4], mines: vec![1, 3] });
+    }
+
+    #[test]
+    fn a_fifty_fifty_proves_nothing() {
+        let (arr, grid) = board("*.\n..\n", &[2, 3]);
+        assert!(deduce(&arr, grid, 1).is_empty());
+        assert!(!is_solvable(&arr, grid, 1, 3));
+    }
+
+    #[test]
+    fn flags_count_as_mines() {
+        // the 1 under the flag is used up, so the other tile next to it is safe
+        let (mut arr, grid) = board("*..\n...\n", &[3, 4]);
+        arr[0].flags = 1;
+        assert_eq!(deduce(&arr, grid, 1), Deductions { safe: vec![1, 2, 5], mines: vec![] });
+    }
+
+    #[test]
+    fn the_mine_count_settles_what_the_numbers_leave_open() {
+        // a revealed 1 1 in the bottom left corner: one mine among tiles 0 and 1
+        let (arr, grid) = board("*...\n....\n", &[4, 5]);
+        assert_eq!(deduce(&arr, grid, 2), Deductions { safe: vec![2, 6], mines: vec![] });
+        // with no other mine in the game, the tiles no number touches are safe too
+        assert_eq!(deduce(&arr, grid, 1), Deductions { safe: vec![2, 3, 6, 7], mines: vec![] });
+    }
+
+    #[test]
+    fn an_opening_that_explains_everything_is_solvable() {
+        let (arr, grid) = board("*...\n....\n....\n...*\n", &[]);
+        assert!(is_solvable(&arr, grid, 2, 6));
+    }
+
+    #[test]
+    fn a_spent_budget_gives_up() {
+        let (arr, grid) = board("*...\n....\n....\n...*\n", &[]);
+        let mut budget = 10;
+        assert_eq!(is_solvable_within(&arr, grid, 2, 6, &mut budget), None);
+    }
+}
@@ -0,0 +1,51 @@
+// Sound effects of the game, embedded into the binary like the built-in sprite
+// sheet and font. The WAV files are made by assets/sounds/generate.py.
+
+use macroquad::audio::{load_sound_from_bytes, play_sound, PlaySoundParams, Sound};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    Reveal,
+    // a reveal or chord that opened more than one tile
+    Cascade,
+    Flag,
+    Chord,
+    Explosion,
+    Fanfare,
+}
+
+// in the order of `Effect`
+const SOUNDS:[&[u8]; 6] = [
+    include_bytes!("../assets/sounds/reveal.wav"),
+    include_bytes!("../assets/sounds/cascade.wav"),
+    include_bytes!("../assets/sounds/flag.wav"),
+    include_bytes!("../assets/sounds/chord.wav"),
+    include_bytes!("../assets/sounds/explosion.wav"),
+    include_bytes!("../assets/sounds/fanfare.wav"),
+];
+
+pub struct Sounds {
+    sounds:Vec<Option<Sound>>,
+}
+
+impl Sounds {
+    // a sound that can't be read is left out, and the game plays on without it
+    pub async fn load() -> Self {
+        let mut sounds = Vec::new();
+        for bytes in SOUNDS {
+            let sound = load_sound_from_bytes(bytes).await;
+            if let Err(err) = &sound {
+                eprintln!("Could not read a sound: {}", err);
+            }
+            sounds.push(sound.ok());
+        }
+        Self { sounds }
+    }
+
+    // volume from 0 to 1, see `Settings::sound_volume`
+    pub fn play(&self, effect:Effect, volume:f32) {
+        if let (Some(sound), true) = (&self.sounds[effect as usize], volume > 0.0) {
+            play_sound(sound, PlaySoundParams { looped: false, volume });
+        }
+    }
+}
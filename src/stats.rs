@@ -0,0 +1,309 @@
+//! History of finished games, for the statistics screen and leaderboards.
+//!
+//! The store is a CSV file so it can be read by spreadsheets and scripts. It
+//! starts with a `# rusty-mines-stats <version>` line and a `#` commented
+//! header naming the columns, followed by one line per finished game, oldest
+//! first:
+//!
+//! | column     | content                                              |
+//! |------------|------------------------------------------------------|
+//! | `date`     | day the game ended, `YYYY-MM-DD` in UTC              |
+//! | `level`    | `Beginner`, `Intermediate`, `Expert` or `Custom`     |
+//! | `rows`     | board height                                         |
+//! | `columns`  | board width                                          |
+//! | `mines`    | number of mines                                      |
+//! | `result`   | `won` or `lost`                                      |
+//! | `seconds`  | time played, with millisecond precision              |
+//! | `3bv`      | 3BV of the board, see [`crate::metrics`]             |
+//! | `clicks`   | reveals, flags and chords made                       |
+//! | `hints`    | hints used                                           |
+//! | `seed`     | seed of the board                                    |
+//! | `practice` | `true` if a move was undone, these don't set records |
+//!
+//! Lines starting with `#` and blank lines are ignored.
+
+use crate::save::SaveError;
+use crate::text::parse;
+
+/// Version written to new stats files.
+pub const STATS_VERSION: u32 = 1;
+
+const HEADER: &str = "# rusty-mines-stats";
+const COLUMNS: &str = "# date,level,rows,columns,mines,result,seconds,3bv,clicks,hints,seed,practice";
+
+/// One finished game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub date: String,
+    pub level: String,
+    pub rows: u16,
+    pub columns: u16,
+    pub mines: u32,
+    pub won: bool,
+    pub seconds: f64,
+    pub three_bv: u32,
+    pub clicks: u32,
+    pub hints: u32,
+    pub seed: u64,
+    pub practice: bool,
+}
+
+impl GameRecord {
+    fn to_line(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{:.3},{},{},{},{},{}",
+            self.date,
+            self.level,
+            self.rows,
+            self.columns,
+            self.mines,
+            if self.won { "won" } else { "lost" },
+            self.seconds,
+            self.three_bv,
+            self.clicks,
+            self.hints,
+            self.seed,
+            self.practice
+        )
+    }
+
+    fn from_line(line: &str) -> Result<Self, SaveError> {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [date, level, rows, columns, mines, result, seconds, three_bv, clicks, hints, seed, practice] = fields[..] else {
+            return Err(SaveError::Invalid("stats line", line.to_owned()));
+        };
+
+        Ok(GameRecord {
+            date: date.to_owned(),
+            level: level.to_owned(),
+            rows: parse("rows", rows)?,
+            columns: parse("columns", columns)?,
+            mines: parse("mines", mines)?,
+            won: match result {
+                "won" => true,
+                "lost" => false,
+                other => return Err(SaveError::Invalid("result", other.to_owned())),
+            },
+            seconds: parse("seconds", seconds)?,
+            three_bv: parse("3bv", three_bv)?,
+            clicks: parse("clicks", clicks)?,
+            hints: parse("hints", hints)?,
+            seed: parse("seed", seed)?,
+            practice: parse("practice", practice)?,
+        })
+    }
+}
+
+/// Totals over a set of games.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub played: u32,
+    pub won: u32,
+    /// Wins in a row up to the most recent game.
+    pub current_streak: u32,
+    pub best_streak: u32,
+    pub best_time: Option<f64>,
+}
+
+impl Summary {
+    /// Share of games won, from 0.0 to 1.0.
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 {
+            0.0
+        } else {
+            self.won as f64 / self.played as f64
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stats {
+    pub games: Vec<GameRecord>,
+}
+
+impl Stats {
+    pub fn push(&mut self, game: GameRecord) {
+        self.games.push(game);
+    }
+
+    /// Totals for one level, or for every game if `level` is None.
+    pub fn summary(&self, level: Option<&str>) -> Summary {
+        let mut summary = Summary::default();
+        for game in self.games.iter().filter(|game| level.is_none_or(|level| game.level == level)) {
+            summary.played += 1;
+            if game.won {
+                summary.won += 1;
+                summary.current_streak += 1;
+                summary.best_streak = summary.best_streak.max(summary.current_streak);
+                if !game.practice {
+                    summary.best_time = Some(summary.best_time.map_or(game.seconds, |best| best.min(game.seconds)));
+                }
+            } else {
+                summary.current_streak = 0;
+            }
+        }
+        summary
+    }
+
+    /// The `count` fastest wins of a level, fastest first, leaving out
+    /// practice games. Earlier games win ties.
+    pub fn leaderboard(&self, level: &str, count: usize) -> Vec<&GameRecord> {
+        let mut wins: Vec<&GameRecord> =
+            self.games.iter().filter(|game| game.won && !game.practice && game.level == level).collect();
+        wins.sort_by(|a, b| a.seconds.total_cmp(&b.seconds));
+        wins.truncate(count);
+        wins
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{HEADER} {STATS_VERSION}\n{COLUMNS}\n");
+        for game in &self.games {
+            text += &game.to_line();
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, SaveError> {
+        let mut lines = text.lines();
+
+        let header = lines.next().ok_or(SaveError::Missing("header"))?;
+        match header.rsplit_once(' ') {
+            Some((HEADER, version)) if version == STATS_VERSION.to_string() => (),
+            Some((HEADER, version)) => return Err(SaveError::UnsupportedVersion(version.to_owned())),
+            _ => return Err(SaveError::Invalid("header", header.to_owned())),
+        }
+
+        let games = lines
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(GameRecord::from_line)
+            .collect::<Result<_, _>>()?;
+        Ok(Stats { games })
+    }
+}
+
+/// `YYYY-MM-DD` date of a Unix timestamp, in UTC.
+pub fn date_from_unix(seconds: f64) -> String {
+    // days since 1970-01-01 to a civil date, after Howard Hinnant's `civil_from_days`
+    let days = (seconds / 86_400.0).floor() as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(level: &str, won: bool, seconds: f64, practice: bool) -> GameRecord {
+        GameRecord {
+            date: "2024-02-29".to_owned(),
+            level: level.to_owned(),
+            rows: 9,
+            columns: 9,
+            mines: 10,
+            won,
+            seconds,
+            three_bv: 14,
+            clicks: 20,
+            hints: 0,
+            seed: 7,
+            practice,
+        }
+    }
+
+    #[test]
+    fn stats_read_back_as_they_were_written() {
+        let stats = Stats {
+            games: vec![game("Beginner", true, 12.5, false), game("Expert", false, 80.125, true)],
+        };
+        assert_eq!(Stats::from_text(&stats.to_text()), Ok(stats));
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_skipped() {
+        let text = "# rusty-mines-stats 1\n# a note\n\n2024-02-29,Beginner,9,9,10,won,12.500,14,20,0,7,false\n";
+        assert_eq!(Stats::from_text(text).unwrap().games, vec![game("Beginner", true, 12.5, false)]);
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        let read = |line: &str| Stats::from_text(&format!("# rusty-mines-stats 1\n{line}\n"));
+        // no practice column
+        let short = "2024-02-29,Beginner,9,9,10,won,12.5,14,20,0,7";
+        assert_eq!(read(short), Err(SaveError::Invalid("stats line", short.to_owned())));
+        assert_eq!(
+            read("2024-02-29,Beginner,9,9,10,draw,12.5,14,20,0,7,false"),
+            Err(SaveError::Invalid("result", "draw".to_owned()))
+        );
+        assert_eq!(
+            read("2024-02-29,Beginner,nine,9,10,won,12.5,14,20,0,7,false"),
+            Err(SaveError::Invalid("rows", "nine".to_owned()))
+        );
+        assert_eq!(
+            read("2024-02-29,Beginner,9,9,10,won,12.5,14,20,0,7,maybe"),
+            Err(SaveError::Invalid("practice", "maybe".to_owned()))
+        );
+        assert_eq!(Stats::from_text("# rusty-mines-stats 2\n"), Err(SaveError::UnsupportedVersion("2".to_owned())));
+        assert!(matches!(Stats::from_text("date,level\n"), Err(SaveError::Invalid("header", _))));
+        assert_eq!(Stats::from_text(""), Err(SaveError::Missing("header")));
+    }
+
+    #[test]
+    fn unix_timestamps_become_utc_dates() {
+        assert_eq!(date_from_unix(0.0), "1970-01-01");
+        assert_eq!(date_from_unix(86_399.9), "1970-01-01");
+        assert_eq!(date_from_unix(951_782_400.0), "2000-02-29");
+        assert_eq!(date_from_unix(1_709_251_199.0), "2024-02-29");
+        assert_eq!(date_from_unix(1_709_251_200.0), "2024-03-01");
+        assert_eq!(date_from_unix(4_107_542_400.0), "2100-03-01");
+        assert_eq!(date_from_unix(-1.0), "1969-12-31");
+    }
+
+    #[test]
+    fn summaries_count_streaks_and_leave_practice_out_of_best_times() {
+        let stats = Stats {
+            games: vec![
+                game("Beginner", true, 30.0, false),
+                game("Beginner", true, 20.0, false),
+                game("Beginner", false, 5.0, false),
+                game("Beginner", true, 10.0, true),
+                game("Expert", true, 90.0, false),
+            ],
+        };
+        let beginner = stats.summary(Some("Beginner"));
+        assert_eq!(
+            beginner,
+            Summary { played: 4, won: 3, current_streak: 1, best_streak: 2, best_time: Some(20.0) }
+        );
+        assert_eq!(beginner.win_rate(), 0.75);
+        assert_eq!(stats.summary(None).current_streak, 2);
+        assert_eq!(Summary::default().win_rate(), 0.0);
+    }
+
+    #[test]
+    fn leaderboards_are_fastest_first_without_practice_or_losses() {
+        let stats = Stats {
+            games: vec![
+                game("Beginner", true, 30.0, false),
+                game("Beginner", true, 10.0, true),
+                game("Beginner", false, 5.0, false),
+                game("Beginner", true, 20.0, false),
+                game("Expert", true, 1.0, false),
+                game("Beginner", true, 20.0, false),
+            ],
+        };
+        let board = stats.leaderboard("Beginner", 2);
+        assert_eq!(board.len(), 2);
+        assert_eq!(board.iter().map(|game| game.seconds).collect::<Vec<_>>(), [20.0, 20.0]);
+        // the earlier of the tied games comes first
+        assert!(std::ptr::eq(board[0], &stats.games[3]));
+        assert_eq!(stats.leaderboard("Beginner", 10).len(), 3);
+    }
+}
@@ -0,0 +1,82 @@
+// Small key/value store for the game binary. Each key is a text file in the
+// user's data directory on desktop, and a browser local storage entry on the
+// web build.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod platform {
+    use std::path::PathBuf;
+
+    // $XDG_DATA_HOME/rusty-mines, ~/.local/share/rusty-mines or %APPDATA%\rusty-mines,
+    // falling back to the working directory
+    fn data_dir() -> PathBuf {
+        let base = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("."));
+        base.join("rusty-mines")
+    }
+
+    fn path(key:&str) -> PathBuf {
+        data_dir().join(key)
+    }
+
+    pub fn load(key:&str) -> Option<String> {
+        std::fs::read_to_string(path(key)).ok()
+    }
+
+    pub fn load_bytes(key:&str) -> Option<Vec<u8>> {
+        std::fs::read(path(key)).ok()
+    }
+
+    pub fn save(key:&str, value:&str) -> Result<(), String> {
+        std::fs::create_dir_all(data_dir()).map_err(|err| err.to_string())?;
+        std::fs::write(path(key), value).map_err(|err| err.to_string())
+    }
+
+    pub fn remove(key:&str) {
+        let _ = std::fs::remove_file(path(key));
+    }
+}
+
+// implemented by the rusty_mines_storage plugin in index.html
+#[cfg(target_arch = "wasm32")]
+mod platform {
+    extern "C" {
+        fn rusty_mines_storage_len(key:*const u8, key_len:usize) -> i32;
+        fn rusty_mines_storage_get(key:*const u8, key_len:usize, out:*mut u8);
+        fn rusty_mines_storage_set(key:*const u8, key_len:usize, value:*const u8, value_len:usize);
+        fn rusty_mines_storage_remove(key:*const u8, key_len:usize);
+    }
+
+    #[no_mangle]
+    extern "C" fn rusty_mines_storage_crate_version() -> u32 {
+        1
+    }
+
+    pub fn load(key:&str) -> Option<String> {
+        let len = unsafe { rusty_mines_storage_len(key.as_ptr(), key.len()) };
+        if len < 0 {
+            return None;
+        }
+        let mut bytes = vec![0u8; len as usize];
+        unsafe { rusty_mines_storage_get(key.as_ptr(), key.len(), bytes.as_mut_ptr()) };
+        String::from_utf8(bytes).ok()
+    }
+
+    // local storage only holds text, so images and fonts can't be put there
+    pub fn load_bytes(_key:&str) -> Option<Vec<u8>> {
+        None
+    }
+
+    pub fn save(key:&str, value:&str) -> Result<(), String> {
+        unsafe { rusty_mines_storage_set(key.as_ptr(), key.len(), value.as_ptr(), value.len()) };
+        Ok(())
+    }
+
+    pub fn remove(key:&str) {
+        unsafe { rusty_mines_storage_remove(key.as_ptr(), key.len()) };
+    }
+}
+
+pub use platform::*;
@@ -0,0 +1,191 @@
+//! Pieces shared by the plain-text file formats: the `<name> <version>` first
+//! line, `key value` fields, and the board settings that saves and
+//! recordings both start with.
+
+use std::str::FromStr;
+
+use crate::save::SaveError;
+use crate::{Board, BoardOptions, Generation, Topology, WinRule};
+
+/// Parses one value, naming `name` in the error.
+pub(crate) fn parse<T: FromStr>(name: &'static str, value: &str) -> Result<T, SaveError> {
+    value.parse().map_err(|_| SaveError::Invalid(name, value.to_owned()))
+}
+
+/// Checks a `<name> <version>` first line. Other versions are rejected rather
+/// than guessed at.
+pub(crate) fn check_header(line: Option<&str>, name: &str, version: u32) -> Result<(), SaveError> {
+    let line = line.ok_or(SaveError::Missing("header"))?;
+    match line.split_once(' ') {
+        Some((found, found_version)) if found == name && found_version == version.to_string() => Ok(()),
+        Some((found, found_version)) if found == name => Err(SaveError::UnsupportedVersion(found_version.to_owned())),
+        _ => Err(SaveError::Invalid("header", line.to_owned())),
+    }
+}
+
+/// `key value` lines, in any order.
+#[derive(Debug, Default)]
+pub(crate) struct Fields<'a> {
+    fields: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Fields<'a> {
+    /// Reads lines up to the `end` line, splitting each at the first
+    /// `separator`. Keys and values are trimmed.
+    pub(crate) fn read(lines: &mut impl Iterator<Item = &'a str>, separator: char, end: &str) -> Result<Self, SaveError> {
+        let mut fields = Vec::new();
+        for line in lines {
+            if line == end {
+                break;
+            }
+            let (key, value) = line.split_once(separator).ok_or_else(|| SaveError::Invalid("line", line.to_owned()))?;
+            fields.push((key.trim(), value.trim()));
+        }
+        Ok(Self { fields })
+    }
+
+    pub(crate) fn get(&self, name: &'static str) -> Result<&'a str, SaveError> {
+        self.fields.iter().find(|(key, _)| *key == name).map(|(_, value)| *value).ok_or(SaveError::Missing(name))
+    }
+
+    pub(crate) fn parse<T: FromStr>(&self, name: &'static str) -> Result<T, SaveError> {
+        parse(name, self.get(name)?)
+    }
+}
+
+/// What it takes to build a fresh board: the settings at the top of saves and
+/// recordings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct BoardSettings {
+    pub rows: u16,
+    pub columns: u16,
+    pub mines: u32,
+    pub seed: u64,
+    pub options: BoardOptions,
+}
+
+impl BoardSettings {
+    pub(crate) fn of(board: &Board) -> Self {
+        Self { rows: board.rows(), columns: board.columns(), mines: board.mines(), seed: board.seed(), options: board.options() }
+    }
+
+    pub(crate) fn new_board(self) -> Board {
+        Board::new(self.rows, self.columns, self.mines, self.seed, self.options)
+    }
+
+    pub(crate) fn to_text(self) -> String {
+        let mut text = format!("rows {}\n", self.rows);
+        text += &format!("columns {}\n", self.columns);
+        text += &format!("mines {}\n", self.mines);
+        text += &format!("seed {}\n", self.seed);
+        text += &format!("generation {}\n", generation_name(self.options.generation));
+        text += &format!("topology {}\n", topology_name(self.options.topology));
+        text += &format!("wrap {}\n", self.options.wrap);
+        text += &format!("multi_mine {}\n", self.options.multi_mine);
+        text += &format!("win_rule {}\n", win_rule_name(self.options.win_rule));
+        text
+    }
+
+    pub(crate) fn from_fields(fields: &Fields) -> Result<Self, SaveError> {
+        let rows: u16 = fields.parse("rows")?;
+        let columns: u16 = fields.parse("columns")?;
+        let mines: u32 = fields.parse("mines")?;
+        if rows == 0 || columns == 0 || mines as usize >= rows as usize * columns as usize {
+            return Err(SaveError::Invalid("board size", format!("{rows}x{columns} with {mines} mines")));
+        }
+        let options = BoardOptions {
+            generation: parse_generation(fields.get("generation")?)?,
+            topology: parse_topology(fields.get("topology")?)?,
+            wrap: fields.parse("wrap")?,
+            multi_mine: fields.parse("multi_mine")?,
+            win_rule: parse_win_rule(fields.get("win_rule")?)?,
+        };
+        Ok(Self { rows, columns, mines, seed: fields.parse("seed")?, options })
+    }
+}
+
+fn generation_name(generation: Generation) -> &'static str {
+    match generation {
+        Generation::Random => "random",
+        Generation::NoGuess => "no-guess",
+    }
+}
+
+fn parse_generation(name: &str) -> Result<Generation, SaveError> {
+    match name {
+        "random" => Ok(Generation::Random),
+        "no-guess" => Ok(Generation::NoGuess),
+        other => Err(SaveError::Invalid("generation", other.to_owned())),
+    }
+}
+
+fn topology_name(topology: Topology) -> &'static str {
+    match topology {
+        Topology::Square => "square",
+        Topology::Hex => "hex",
+    }
+}
+
+fn parse_topology(name: &str) -> Result<Topology, SaveError> {
+    match name {
+        "square" => Ok(Topology::Square),
+        "hex" => Ok(Topology::Hex),
+        other => Err(SaveError::Invalid("topology", other.to_owned())),
+    }
+}
+
+fn win_rule_name(rule: WinRule) -> &'static str {
+    match rule {
+        WinRule::RevealSafe => "reveal",
+        WinRule::FlagMines => "flag",
+    }
+}
+
+fn parse_win_rule(name: &str) -> Result<WinRule, SaveError> {
+    match name {
+        "reveal" => Ok(WinRule::RevealSafe),
+        "flag" => Ok(WinRule::FlagMines),
+        other => Err(SaveError::Invalid("win rule", other.to_owned())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn board_settings_read_back_what_they_write() {
+        let options = BoardOptions {
+            generation: Generation::NoGuess,
+            topology: Topology::Hex,
+            wrap: true,
+            multi_mine: false,
+            win_rule: WinRule::FlagMines,
+        };
+        let settings = BoardSettings { rows: 9, columns: 12, mines: 20, seed: 42, options };
+        let text = settings.to_text();
+        let fields = Fields::read(&mut text.lines(), ' ', "end").unwrap();
+        assert_eq!(BoardSettings::from_fields(&fields), Ok(settings));
+    }
+
+    #[test]
+    fn every_board_setting_is_required() {
+        let text = BoardSettings::of(&Board::new(8, 8, 10, 1, BoardOptions::default())).to_text();
+        for skipped in 0..text.lines().count() {
+            let mut lines = text.lines().enumerate().filter(|(i, _)| *i != skipped).map(|(_, line)| line);
+            let fields = Fields::read(&mut lines, ' ', "end").unwrap();
+            assert!(matches!(BoardSettings::from_fields(&fields), Err(SaveError::Missing(_))), "line {skipped}");
+        }
+    }
+
+    #[test]
+    fn headers_need_the_right_name_and_version() {
+        assert_eq!(check_header(Some("rusty-mines-save 1"), "rusty-mines-save", 1), Ok(()));
+        assert_eq!(
+            check_header(Some("rusty-mines-save 2"), "rusty-mines-save", 1),
+            Err(SaveError::UnsupportedVersion("2".to_owned()))
+        );
+        assert!(check_header(Some("rusty-mines-replay 1"), "rusty-mines-save", 1).is_err());
+        assert_eq!(check_header(None, "rusty-mines-save", 1), Err(SaveError::Missing("header")));
+    }
+}
@@ -0,0 +1,312 @@
+// Looks of the game: where the sprites are in the sprite sheet, the font, and
+// the colours of the tiles and the interface. A theme file has one setting per
+// line, and lines starting with `#` are comments:
+//
+//   name Dark                  shown on the settings screen
+//   base Rusty                 built-in theme to start from, the first one if left out
+//   sprite_sheet sheet.png     PNG next to the save, the built-in sheet if left out
+//   font font.ttf              TTF next to the save, the built-in font if left out
+//   sprite 1 0 0 40 40         x y width height in the sheet of 1 to 8, bomb, explosion or flag
+//   color hidden #c0c0c0       #rrggbb or #rrggbbaa of one of the colours in `Palette`
+//
+// The built-in themes are theme files in assets/themes, and a player's own theme
+// goes in theme.txt next to the save. It only needs the settings it changes.
+
+use macroquad::prelude::{Color, Rect};
+
+// in the order the settings screen shows them, the first one is the default
+// and sets everything
+const BUILT_IN:[&str; 4] = [
+    include_str!("../assets/themes/rusty.theme"),
+    include_str!("../assets/themes/classic.theme"),
+    include_str!("../assets/themes/dark.theme"),
+    include_str!("../assets/themes/high-contrast.theme"),
+];
+
+// where each sprite is in the sprite sheet
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sprites {
+    pub numbers:[Rect; 8],
+    pub bomb:Rect,
+    pub explosion:Rect,
+    pub flag:Rect,
+}
+
+impl Sprites {
+    pub fn all(&self) -> impl Iterator<Item = Rect> + '_ {
+        self.numbers.iter().copied().chain([self.bomb, self.explosion, self.flag])
+    }
+
+    fn get_mut(&mut self, name:&str) -> Option<&mut Rect> {
+        match name {
+            "bomb" => Some(&mut self.bomb),
+            "explosion" => Some(&mut self.explosion),
+            "flag" => Some(&mut self.flag),
+            _ => match name.parse::<usize>() {
+                Ok(number @ 1..=8) => Some(&mut self.numbers[number - 1]),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Palette {
+    pub background:Color,
+    pub hidden:Color,
+    pub revealed:Color,
+    // behind the mines shown once a game is over, and in the editor
+    pub mine:Color,
+    pub outline:Color,
+    // cross over the flags that were wrong
+    pub wrong_flag:Color,
+    // numbers above 8, which have no sprite
+    pub number:Color,
+    // multiplied with the sprites
+    pub sprite_tint:Color,
+    pub hint:Color,
+    pub cursor:Color,
+    pub text:Color,
+    pub text_dim:Color,
+    // practice games, errors, losing and the button that was picked
+    pub alert:Color,
+    pub success:Color,
+    // box behind the end of game text, and the text in it
+    pub overlay:Color,
+    pub overlay_text:Color,
+    pub button:Color,
+    pub button_hover:Color,
+    // buttons of settings that are switched on
+    pub button_on:Color,
+    pub button_disabled:Color,
+}
+
+impl Palette {
+    fn get_mut(&mut self, name:&str) -> Option<&mut Color> {
+        let color = match name {
+            "background" => &mut self.background,
+            "hidden" => &mut self.hidden,
+            "revealed" => &mut self.revealed,
+            "mine" => &mut self.mine,
+            "outline" => &mut self.outline,
+            "wrong_flag" => &mut self.wrong_flag,
+            "number" => &mut self.number,
+            "sprite_tint" => &mut self.sprite_tint,
+            "hint" => &mut self.hint,
+            "cursor" => &mut self.cursor,
+            "text" => &mut self.text,
+            "text_dim" => &mut self.text_dim,
+            "alert" => &mut self.alert,
+            "success" => &mut self.success,
+            "overlay" => &mut self.overlay,
+            "overlay_text" => &mut self.overlay_text,
+            "button" => &mut self.button,
+            "button_hover" => &mut self.button_hover,
+            "button_on" => &mut self.button_on,
+            "button_disabled" => &mut self.button_disabled,
+            _ => return None,
+        };
+        Some(color)
+    }
+}
+
+// colour vision the game can make up for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorVision {
+    #[default]
+    Normal,
+    // deuteranopia and protanopia
+    RedGreen,
+    // tritanopia
+    BlueYellow,
+}
+
+impl ColorVision {
+    pub const ALL:[ColorVision; 3] = [ColorVision::Normal, ColorVision::RedGreen, ColorVision::BlueYellow];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorVision::Normal => "normal",
+            ColorVision::RedGreen => "red-green",
+            ColorVision::BlueYellow => "blue-yellow",
+        }
+    }
+
+    pub fn from_name(name:&str) -> Option<Self> {
+        Self::ALL.into_iter().find(|vision| vision.name() == name)
+    }
+}
+
+// colours that stay apart for one kind of colour blindness, replacing the
+// theme's wherever colour alone tells things apart
+#[derive(Debug, Clone, PartialEq)]
+pub struct SafeColors {
+    pub numbers:[Color; 8],
+    // ends of the mine probability heatmap
+    pub safe:Color,
+    pub mine:Color,
+    pub hint:Color,
+    pub success:Color,
+    pub alert:Color,
+}
+
+impl SafeColors {
+    // based on the Okabe-Ito palette, with neighbouring numbers also differing in lightness
+    pub fn for_vision(vision:ColorVision) -> Option<Self> {
+        let hex = |hex:u32| Color::from_hex(hex);
+        match vision {
+            ColorVision::Normal => None,
+            ColorVision::RedGreen => Some(Self {
+                numbers:[0x0072b2, 0xe69f00, 0xcc79a7, 0x003f7f, 0x8a5a00, 0x56b4e9, 0x000000, 0x6e6e6e].map(hex),
+                safe:hex(0x0072b2),
+                mine:hex(0xe69f00),
+                hint:hex(0x56b4e9),
+                success:hex(0x56b4e9),
+                alert:hex(0xe69f00),
+            }),
+            ColorVision::BlueYellow => Some(Self {
+                numbers:[0x007480, 0xd1004a, 0x5a005a, 0x004b52, 0x8c1c13, 0xff5c8a, 0x000000, 0x6e6e6e].map(hex),
+                safe:hex(0x009e9e),
+                mine:hex(0xd1004a),
+                hint:hex(0x00c8c8),
+                success:hex(0x00c8c8),
+                alert:hex(0xff3b6b),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Theme {
+    pub name:String,
+    // files next to the save, None for the ones built into the game
+    pub sprite_sheet:Option<String>,
+    pub font:Option<String>,
+    pub sprites:Sprites,
+    pub colors:Palette,
+}
+
+impl Theme {
+    pub fn built_in() -> Vec<Theme> {
+        let mut themes = Vec::new();
+        for text in BUILT_IN {
+            let theme = Theme::from_text(text, &themes).expect("built-in themes are valid");
+            themes.push(theme);
+        }
+        themes
+    }
+
+    // reads a theme file on top of its base, one of `themes`
+    pub fn from_text(text:&str, themes:&[Theme]) -> Result<Self, String> {
+        let lines:Vec<(usize, &str, &str)> = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(number, line)| {
+                let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+                (number, key, value.trim())
+            })
+            .collect();
+
+        let mut theme = match lines.iter().find(|(_, key, _)| *key == "base") {
+            Some((number, _, name)) => {
+                themes.iter().find(|theme| theme.name == *name).cloned().ok_or(format!("line {}: no built-in theme {:?}", number, name))?
+            }
+            None => themes.first().cloned().unwrap_or_default(),
+        };
+
+        for (number, key, value) in lines {
+            let invalid = || format!("line {}: invalid {} {:?}", number, key, value);
+            match key {
+                "base" => (),
+                "name" if !value.is_empty() => theme.name = value.to_owned(),
+                "sprite_sheet" if !value.is_empty() => theme.sprite_sheet = Some(value.to_owned()),
+                "font" if !value.is_empty() => theme.font = Some(value.to_owned()),
+                "sprite" => {
+                    let (name, rect) = value.split_once(' ').ok_or_else(invalid)?;
+                    let sprite = theme.sprites.get_mut(name).ok_or_else(invalid)?;
+                    *sprite = parse_rect(rect).ok_or_else(invalid)?;
+                }
+                "color" => {
+                    let (name, hex) = value.split_once(' ').ok_or_else(invalid)?;
+                    let color = theme.colors.get_mut(name).ok_or_else(invalid)?;
+                    *color = parse_color(hex.trim()).ok_or_else(invalid)?;
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(theme)
+    }
+}
+
+// x y width height, with a size
+fn parse_rect(text:&str) -> Option<Rect> {
+    let numbers:Vec<f32> = text.split_whitespace().map(|number| number.parse::<u16>().map(f32::from)).collect::<Result<_, _>>().ok()?;
+    match numbers[..] {
+        [x, y, w, h] if w > 0.0 && h > 0.0 => Some(Rect::new(x, y, w, h)),
+        _ => None,
+    }
+}
+
+// #rrggbb or #rrggbbaa
+fn parse_color(text:&str) -> Option<Color> {
+    let hex = text.strip_prefix('#')?;
+    if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i:usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Some(Color::from_rgba(channel(0)?, channel(2)?, channel(4)?, alpha))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_built_in_themes_read_and_have_their_own_names() {
+        let themes = Theme::built_in();
+        let names:Vec<&str> = themes.iter().map(|theme| theme.name.as_str()).collect();
+        assert_eq!(names, ["Rusty", "Classic", "Dark", "High contrast"]);
+        // the default sets every sprite, so the others have something to start from
+        assert!(themes[0].sprites.all().all(|sprite| sprite.w > 0.0 && sprite.h > 0.0));
+        assert_eq!(themes[0].colors.sprite_tint, Color::from_rgba(255, 255, 255, 255));
+    }
+
+    #[test]
+    fn a_theme_file_only_changes_what_it_lists() {
+        let themes = Theme::built_in();
+        let theme = Theme::from_text("# mine\nname Mine\nbase Dark\ncolor hidden #102030\nsprite flag 1 2 3 4\n", &themes).unwrap();
+        assert_eq!(theme.name, "Mine");
+        assert_eq!(theme.colors.hidden, Color::from_rgba(0x10, 0x20, 0x30, 255));
+        assert_eq!(theme.sprites.flag, Rect::new(1.0, 2.0, 3.0, 4.0));
+        assert_eq!(theme.colors.revealed, themes[2].colors.revealed);
+        assert_eq!(theme.sprites.bomb, themes[0].sprites.bomb);
+    }
+
+    #[test]
+    fn mistakes_name_their_line() {
+        let themes = Theme::built_in();
+        assert_eq!(Theme::from_text("name X\ncolor hiden #ffffff", &themes), Err("line 2: invalid color \"hiden #ffffff\"".to_owned()));
+        assert!(Theme::from_text("color hidden #fff", &themes).is_err());
+        assert!(Theme::from_text("sprite 9 0 0 40 40", &themes).is_err());
+        assert!(Theme::from_text("sprite bomb 0 0 0 40", &themes).is_err());
+        assert!(Theme::from_text("base Missing", &themes).is_err());
+        assert!(Theme::from_text("colour hidden #ffffff", &themes).is_err());
+    }
+
+    #[test]
+    fn colour_blind_palettes_keep_every_number_apart() {
+        for vision in ColorVision::ALL {
+            assert_eq!(ColorVision::from_name(vision.name()), Some(vision));
+            let Some(safe) = SafeColors::for_vision(vision) else {
+                continue;
+            };
+            for (i, color) in safe.numbers.iter().enumerate() {
+                assert!(!safe.numbers[i + 1..].contains(color), "{} repeats number {}", vision.name(), i + 1);
+            }
+            assert_ne!(safe.safe, safe.mine);
+        }
+    }
+}
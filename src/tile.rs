@@ -0,0 +1,24 @@
+/// A single cell of the minefield.
+///
+/// `mines` and `flags` are 0 or 1 on classic boards, and go up to
+/// [`crate::rules::MAX_MINES_PER_TILE`] on multi-mine boards.
+/// `adjacent_mines` is counted for every tile once the mines are placed, by
+/// [`crate::rules::count_adjacent_mines`], but only shown once the tile is
+/// revealed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tile {
+    pub revealed: bool,
+    pub mines: u8,
+    pub flags: u8,
+    pub adjacent_mines: u8,
+}
+
+impl Tile {
+    pub fn has_mine(&self) -> bool {
+        self.mines > 0
+    }
+
+    pub fn flagged(&self) -> bool {
+        self.flags > 0
+    }
+}
@@ -0,0 +1,115 @@
+//! Which tiles touch which.
+//!
+//! Every rule that looks at neighbours (counting mines, flood fills, chords,
+//! the solver and 3BV) asks a [`Grid`] for them instead of walking the 3x3
+//! block around a tile, so the same rules play on square and hexagonal
+//! boards.
+//!
+//! Hexagonal boards are stored in the same rows and columns as square ones,
+//! with odd rows shifted half a tile to the right ("odd-r" offset layout).
+//! A tile then touches two tiles in the row above, two in its own row and
+//! two in the row below.
+//!
+//! A wrapping grid is a torus: the left edge touches the right edge and the
+//! top touches the bottom. Hex rows only line up across the top and bottom
+//! edges when there is an even number of them, so hex grids with an odd
+//! number of rows only wrap left to right. On grids too small for the steps
+//! to reach distinct tiles a neighbour is only listed once, and a tile is
+//! never its own neighbour.
+
+/// How the tiles of a board are laid out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Topology {
+    /// Square tiles with 8 neighbours.
+    #[default]
+    Square,
+    /// Pointy-topped hexagons with 6 neighbours.
+    Hex,
+}
+
+// (row, column) steps to the neighbours
+const SQUARE_NEIGHBOURS: [(i32, i32); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+const HEX_EVEN_ROW_NEIGHBOURS: [(i32, i32); 6] = [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)];
+const HEX_ODD_ROW_NEIGHBOURS: [(i32, i32); 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)];
+
+impl Topology {
+    fn steps(self, row: i32) -> &'static [(i32, i32)] {
+        match self {
+            Topology::Square => &SQUARE_NEIGHBOURS,
+            Topology::Hex if row % 2 == 0 => &HEX_EVEN_ROW_NEIGHBOURS,
+            Topology::Hex => &HEX_ODD_ROW_NEIGHBOURS,
+        }
+    }
+}
+
+/// Size and layout of a grid of tiles, stored row by row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Grid {
+    pub rows: u16,
+    pub cols: u16,
+    pub topology: Topology,
+    /// Whether neighbours wrap around the edges.
+    pub wrap: bool,
+}
+
+impl Grid {
+    pub fn new(rows: u16, cols: u16, topology: Topology, wrap: bool) -> Self {
+        Self { rows, cols, topology, wrap }
+    }
+
+    pub fn wraps_columns(&self) -> bool {
+        self.wrap
+    }
+
+    pub fn wraps_rows(&self) -> bool {
+        self.wrap && (self.topology == Topology::Square || self.rows.is_multiple_of(2))
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.rows as usize * self.cols as usize
+    }
+
+    pub fn contains(&self, row: i32, col: i32) -> bool {
+        row >= 0 && row < self.rows as i32 && col >= 0 && col < self.cols as i32
+    }
+
+    /// Converts a row and column to a tile id, if it lies on the grid.
+    pub fn tile_id(&self, row: i32, col: i32) -> Option<usize> {
+        self.contains(row, col).then(|| (row * self.cols as i32 + col) as usize)
+    }
+
+    /// Row and column of a tile.
+    pub fn position(&self, tile_id: usize) -> (i32, i32) {
+        ((tile_id / self.cols as usize) as i32, (tile_id % self.cols as usize) as i32)
+    }
+
+    /// Tiles touching `tile_id`, each listed once, in ascending order.
+    pub fn neighbours(&self, tile_id: usize) -> impl Iterator<Item = usize> {
+        let (row, col) = self.position(tile_id);
+        let mut found = [0; 8];
+        let mut count = 0;
+        for &(r, c) in self.topology.steps(row) {
+            let Some(next_id) = self.step(row + r, col + c) else {
+                continue;
+            };
+            if next_id != tile_id && !found[..count].contains(&next_id) {
+                found[count] = next_id;
+                count += 1;
+            }
+        }
+        // the solver relies on the order, which wrapped steps would break
+        found[..count].sort_unstable();
+        found.into_iter().take(count)
+    }
+
+    // tile a neighbour step lands on, following the wrap
+    fn step(&self, mut row: i32, mut col: i32) -> Option<usize> {
+        if self.wraps_rows() {
+            row = row.rem_euclid(self.rows as i32);
+        }
+        if self.wraps_columns() {
+            col = col.rem_euclid(self.cols as i32);
+        }
+        self.tile_id(row, col)
+    }
+}
@@ -0,0 +1,165 @@
+//! Property tests of the rules on random boards of every shape.
+
+use std::collections::HashSet;
+
+use minesweeper::rules::{
+    count_adjacent_mines, flag_tile, num_adjacent_mines, reveal_all_adjacent_tiles, reveal_tile, update_game_state,
+};
+use minesweeper::{Board, BoardOptions, BoardState, Grid, Tile, Topology, WinRule};
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+// a small grid of any shape, with roughly one tile in five mined
+fn grid_and_tiles() -> impl Strategy<Value = (Grid, Vec<Tile>)> {
+    (1u16..10, 1u16..10, any::<bool>(), any::<bool>())
+        .prop_flat_map(|(rows, cols, hex, wrap)| {
+            let topology = if hex { Topology::Hex } else { Topology::Square };
+            let grid = Grid::new(rows, cols, topology, wrap);
+            (Just(grid), vec(prop::bool::weighted(0.2), grid.tile_count()))
+        })
+        .prop_map(|(grid, mines)| {
+            let mut tiles: Vec<Tile> = mines.into_iter().map(|mine| Tile { mines: mine as u8, ..Tile::default() }).collect();
+            count_adjacent_mines(&mut tiles, grid);
+            (grid, tiles)
+        })
+}
+
+fn safe_tiles(arr: &[Tile]) -> Vec<usize> {
+    (0..arr.len()).filter(|&tile_id| !arr[tile_id].has_mine()).collect()
+}
+
+fn state_after(arr: &[Tile], rule: WinRule) -> BoardState {
+    let mut state = BoardState::Playing;
+    update_game_state(arr, &mut state, rule);
+    state
+}
+
+// what a flood fill from `start` should reveal: every safe tile reachable
+// through tiles without adjacent mines
+fn expected_opening(arr: &[Tile], grid: Grid, start: usize) -> HashSet<usize> {
+    let mut opened = HashSet::from([start]);
+    let mut stack = vec![start];
+    while let Some(tile_id) = stack.pop() {
+        if num_adjacent_mines(arr, grid, tile_id) > 0 {
+            continue;
+        }
+        for next_id in grid.neighbours(tile_id) {
+            if !arr[next_id].has_mine() && opened.insert(next_id) {
+                stack.push(next_id);
+            }
+        }
+    }
+    opened
+}
+
+proptest! {
+    #[test]
+    fn the_flood_fill_reveals_exactly_the_opening((grid, mut arr) in grid_and_tiles(), pick in any::<prop::sample::Index>()) {
+        let safe = safe_tiles(&arr);
+        prop_assume!(!safe.is_empty());
+        let start = safe[pick.index(safe.len())];
+
+        let expected = expected_opening(&arr, grid, start);
+        reveal_tile(&mut arr, start, grid);
+        let revealed: HashSet<usize> = (0..arr.len()).filter(|&tile_id| arr[tile_id].revealed).collect();
+        prop_assert_eq!(revealed, expected);
+    }
+
+    #[test]
+    fn revealed_tiles_show_their_neighbouring_mines((grid, mut arr) in grid_and_tiles(), pick in any::<prop::sample::Index>()) {
+        let safe = safe_tiles(&arr);
+        prop_assume!(!safe.is_empty());
+        reveal_tile(&mut arr, safe[pick.index(safe.len())], grid);
+
+        for tile_id in 0..arr.len() {
+            if !arr[tile_id].revealed {
+                continue;
+            }
+            prop_assert!(!arr[tile_id].has_mine());
+            prop_assert_eq!(arr[tile_id].adjacent_mines, num_adjacent_mines(&arr, grid, tile_id));
+            // an opening leaves no hidden neighbours behind
+            if arr[tile_id].adjacent_mines == 0 {
+                prop_assert!(grid.neighbours(tile_id).all(|next_id| arr[next_id].revealed));
+            }
+        }
+        prop_assert_ne!(state_after(&arr, WinRule::RevealSafe), BoardState::Lost);
+    }
+
+    #[test]
+    fn revealing_every_safe_tile_wins_in_any_order((grid, mut arr) in grid_and_tiles(), order in any::<u64>()) {
+        let mut safe = safe_tiles(&arr);
+        prop_assume!(!safe.is_empty());
+        let len = safe.len();
+        safe.rotate_left(order as usize % len);
+
+        let mut state = BoardState::Playing;
+        for &tile_id in &safe {
+            reveal_tile(&mut arr, tile_id, grid);
+            update_game_state(&arr, &mut state, WinRule::RevealSafe);
+            // won exactly when the last safe tile is out, which a flood fill may do early
+            let all_revealed = safe.iter().all(|&safe_id| arr[safe_id].revealed);
+            prop_assert_eq!(state == BoardState::Won, all_revealed);
+        }
+        prop_assert_eq!(state, BoardState::Won);
+    }
+
+    #[test]
+    fn the_flag_rule_needs_exactly_the_mines_flagged((_, mut arr) in grid_and_tiles(), flags in vec(0u8..2, 81)) {
+        prop_assume!(!safe_tiles(&arr).is_empty());
+        for (tile, &flags) in arr.iter_mut().zip(&flags) {
+            tile.flags = flags;
+        }
+        let exact = arr.iter().all(|tile| tile.flags == tile.mines);
+        let expected = if exact { BoardState::Won } else { BoardState::Playing };
+        prop_assert_eq!(state_after(&arr, WinRule::FlagMines), expected);
+        // flags never decide the reveal rule
+        prop_assert_eq!(state_after(&arr, WinRule::RevealSafe), BoardState::Playing);
+    }
+
+    #[test]
+    fn chording_around_correct_flags_never_hits_a_mine((grid, mut arr) in grid_and_tiles(), pick in any::<prop::sample::Index>()) {
+        let safe = safe_tiles(&arr);
+        prop_assume!(!safe.is_empty());
+        let mines = arr.iter().filter(|tile| tile.has_mine()).count() as u32;
+        for tile_id in 0..arr.len() {
+            if arr[tile_id].has_mine() {
+                prop_assert!(flag_tile(&mut arr, tile_id, mines, 1));
+            }
+        }
+        reveal_tile(&mut arr, safe[pick.index(safe.len())], grid);
+
+        // chording every revealed number, over and over, opens everything not walled off by mines
+        loop {
+            let before: Vec<Tile> = arr.clone();
+            for tile_id in 0..arr.len() {
+                reveal_all_adjacent_tiles(&mut arr, tile_id, grid);
+            }
+            prop_assert_ne!(state_after(&arr, WinRule::RevealSafe), BoardState::Lost);
+            if arr == before {
+                break;
+            }
+        }
+        for tile_id in (0..arr.len()).filter(|&tile_id| arr[tile_id].revealed) {
+            prop_assert!(grid.neighbours(tile_id).all(|next_id| arr[next_id].revealed || arr[next_id].flagged()));
+        }
+    }
+
+    #[test]
+    fn board_flags_never_outnumber_the_mines(seed in any::<u64>(), moves in vec((0usize..64, any::<bool>()), 1..40), multi_mine in any::<bool>()) {
+        let options = BoardOptions { multi_mine, win_rule: WinRule::FlagMines, ..BoardOptions::default() };
+        let mut board = Board::new(8, 8, 10, seed, options);
+        for (tile_id, reveal) in moves {
+            if reveal {
+                board.reveal(tile_id);
+            } else {
+                board.flag(tile_id);
+            }
+            let flags: u32 = board.tiles().iter().map(|tile| tile.flags as u32).sum();
+            prop_assert_eq!(flags, board.mines_flagged());
+            prop_assert!(board.mines_flagged() <= board.mines());
+            if board.state() == BoardState::Won {
+                prop_assert!(board.tiles().iter().all(|tile| tile.flags == tile.mines));
+            }
+        }
+    }
+}